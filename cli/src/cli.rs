@@ -94,6 +94,9 @@ pub enum SubCmd {
     GetEvents(GetEventsCommand),
     SetKey(SetKeyCommand),
     GetBalances(GetBalancesCommand),
+    #[command(about = "Record the admins built in the nucleus if there is none")]
+    BootstrapAdmins,
+    BackfillFeed(BackfillFeedCommand),
 }
 
 #[derive(Debug, Parser)]
@@ -145,23 +148,21 @@ pub struct CommunityCommand {
     pub token_total_supply: u64,
 }
 
-use flate2::write::GzEncoder;
-use flate2::Compression;
-use std::io::prelude::*;
+use vemodel::{Compression, ContentFormat};
 
 impl Into<vemodel::args::CreateCommunityArg> for CommunityCommand {
     fn into(self) -> vemodel::args::CreateCommunityArg {
         vemodel::args::CreateCommunityArg {
             name: self.name,
             logo: Default::default(),
-            private: false,
+            mode: vemodel::CommunityMode::Public,
             slug: self.slug,
             token: vemodel::args::TokenMetadataArg {
                 name: "".to_string(),
                 symbol: self.token_name,
                 decimals: self.token_decimals,
                 new_issue: true,
-                total_issuance: self.token_total_supply,
+                total_issuance: self.token_total_supply as u128,
                 image: None,
                 contract: None,
             },
@@ -170,6 +171,7 @@ impl Into<vemodel::args::CreateCommunityArg> for CommunityCommand {
             llm_name: "OpenAI".to_string(),
             llm_api_host: None,
             llm_key: None,
+            generation: Default::default(),
         }
     }
 }
@@ -192,20 +194,32 @@ pub struct ThreadCommand {
     pub title: String,
     #[arg(long)]
     pub content: String,
+    #[arg(long, default_value = "markdown")]
+    pub format: ContentFormat,
+    #[arg(long, default_value = "gzip", value_parser = parse_compression)]
+    pub compression: Compression,
 }
 
-impl Into<vemodel::args::PostThreadArg> for ThreadCommand {
-    fn into(self) -> vemodel::args::PostThreadArg {
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-        encoder.write_all(self.content.as_bytes()).unwrap();
-        let stream = encoder.finish().unwrap();
-        vemodel::args::PostThreadArg {
-            community: self.community,
-            title: self.title,
+/// zstd could be decoded but not encoded
+fn parse_compression(s: &str) -> Result<Compression, String> {
+    match s.parse::<Compression>()? {
+        Compression::Zstd => Err("zstd encoding is not supported, use gzip or none".to_string()),
+        compression => Ok(compression),
+    }
+}
+
+impl TryFrom<ThreadCommand> for vemodel::args::PostThreadArg {
+    type Error = String;
+
+    fn try_from(cmd: ThreadCommand) -> Result<Self, Self::Error> {
+        let stream = vemodel::codec::encode(&cmd.content, cmd.format, cmd.compression)?;
+        Ok(vemodel::args::PostThreadArg {
+            community: cmd.community,
+            title: cmd.title,
             content: stream,
             images: vec![],
             mention: vec![],
-        }
+        })
     }
 }
 
@@ -216,28 +230,72 @@ pub struct CommentCommand {
     pub thread: String,
     #[arg(long)]
     pub content: String,
+    #[arg(long, default_value = "markdown")]
+    pub format: ContentFormat,
+    #[arg(long, default_value = "gzip", value_parser = parse_compression)]
+    pub compression: Compression,
 }
 
-impl Into<vemodel::args::PostCommentArg> for CommentCommand {
-    fn into(self) -> vemodel::args::PostCommentArg {
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-        encoder.write_all(self.content.as_bytes()).unwrap();
-        let stream = encoder.finish().unwrap();
-        vemodel::args::PostCommentArg {
-            thread: self.thread.parse().expect("invalid thread id"),
+impl TryFrom<CommentCommand> for vemodel::args::PostCommentArg {
+    type Error = String;
+
+    fn try_from(cmd: CommentCommand) -> Result<Self, Self::Error> {
+        let stream = vemodel::codec::encode(&cmd.content, cmd.format, cmd.compression)?;
+        Ok(vemodel::args::PostCommentArg {
+            thread: cmd
+                .thread
+                .parse()
+                .map_err(|_| "invalid thread id".to_string())?,
             content: stream,
             images: vec![],
             mention: vec![],
             reply_to: None,
-        }
+        })
     }
 }
 
 #[derive(Debug, Parser)]
 #[command(about = "Set a LLM key")]
 pub struct SetKeyCommand {
+    #[arg(
+        long,
+        default_value = "OpenAI",
+        help = "The LLM vendor, OpenAI or DeepSeek"
+    )]
+    pub vendor: String,
+
     #[arg(long)]
-    pub key: String,
+    pub llm_key: String,
+
+    #[arg(long, help = "The ECDSA private key of a platform admin")]
+    pub signer: String,
+}
+
+impl SetKeyCommand {
+    pub(crate) fn get_signer(&self) -> Result<secp256k1::SecretKey, String> {
+        let key = hex::decode(self.signer.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+        secp256k1::SecretKey::from_slice(&key).map_err(|_| "invalid signer key".to_string())
+    }
+}
+
+#[derive(Debug, Parser)]
+#[command(about = "Index the threads posted before the feed into the feed index")]
+pub struct BackfillFeedCommand {
+    #[arg(long, default_value_t = 0, help = "The content id to start from")]
+    pub from: ContentId,
+
+    #[arg(long, default_value_t = 1000, help = "The contents scanned per call")]
+    pub limit: u32,
+
+    #[arg(long, help = "The ECDSA private key of a platform admin")]
+    pub signer: String,
+}
+
+impl BackfillFeedCommand {
+    pub(crate) fn get_signer(&self) -> Result<secp256k1::SecretKey, String> {
+        let key = hex::decode(self.signer.trim_start_matches("0x")).map_err(|e| e.to_string())?;
+        secp256k1::SecretKey::from_slice(&key).map_err(|_| "invalid signer key".to_string())
+    }
 }
 
 #[derive(Debug, Parser)]
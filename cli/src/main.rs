@@ -4,22 +4,90 @@ use ed25519_dalek::{Signer, SigningKey};
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
 use jsonrpsee::{core::client::ClientT, rpc_params};
 use parity_scale_codec::{Decode, Encode};
-use vemodel::{args::*, *};
+use secp256k1::{Message, Secp256k1, SecretKey};
+use tiny_keccak::{Hasher, Keccak};
+use vemodel::{args::*, crypto::*, *};
 use vrs_core_sdk::NucleusId;
 
-// TODO
-pub async fn set_openai_key<T: ClientT>(
+fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut keccak = Keccak::v256();
+    keccak.update(data);
+    let mut hash = [0u8; 32];
+    keccak.finalize(&mut hash);
+    hash
+}
+
+/// the ethereum address of the ECDSA key
+fn to_account_id(signer: &SecretKey) -> AccountId {
+    let pubkey = signer.public_key(&Secp256k1::signing_only());
+    let hash = keccak256(&pubkey.serialize_uncompressed()[1..]);
+    H160(hash[12..].try_into().expect("qed"))
+}
+
+/// sign the payload with the current nonce of the signer
+async fn sign<T: ClientT, P: Encode>(
+    client: &T,
+    nucleus_id: &NucleusId,
+    payload: P,
+    signer: &SecretKey,
+) -> Result<Args<P, EcdsaSignature>, Box<dyn std::error::Error>> {
+    let account_id = to_account_id(signer);
+    let account = get_account_info(client, nucleus_id, account_id).await?;
+    let mut args = Args {
+        signature: EcdsaSignature([0u8; 65]),
+        signer: account_id,
+        nonce: account.nonce,
+        payload,
+    };
+    let message = Message::from_digest(keccak256(&args.to_be_signed()));
+    let (rid, rs) = Secp256k1::signing_only()
+        .sign_ecdsa_recoverable(&message, signer)
+        .serialize_compact();
+    let mut signature = [0u8; 65];
+    signature[..64].copy_from_slice(&rs);
+    signature[64] = i32::from(rid) as u8;
+    args.signature = EcdsaSignature(signature);
+    Ok(args)
+}
+
+pub async fn set_llm_key<T: ClientT>(
     client: T,
     nucleus_id: NucleusId,
-    key: String,
+    arg: SetLlmKeyArg,
+    signer: SecretKey,
 ) -> Result<(), Box<dyn std::error::Error>> {
-    let payload = hex::encode(key.encode());
+    let args = sign(&client, &nucleus_id, arg, &signer).await?;
+    let payload = hex::encode(args.encode());
     let params = rpc_params![nucleus_id.to_string(), "set_llm_key", payload];
     let hex_str: String = client.request("nucleus_post", params).await?;
     let hex = hex::decode(&hex_str)?;
     Result::<(), String>::decode(&mut &hex[..])?.map_err(|e| e.into())
 }
 
+pub async fn bootstrap_admins<T: ClientT>(
+    client: T,
+    nucleus_id: NucleusId,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let params = rpc_params![nucleus_id.to_string(), "bootstrap_admins", ""];
+    let hex_str: String = client.request("nucleus_post", params).await?;
+    let hex = hex::decode(&hex_str)?;
+    Result::<(), String>::decode(&mut &hex[..])?.map_err(|e| e.into())
+}
+
+pub async fn backfill_feed<T: ClientT>(
+    client: &T,
+    nucleus_id: &NucleusId,
+    arg: BackfillFeedArg,
+    signer: &SecretKey,
+) -> Result<Option<ContentId>, Box<dyn std::error::Error>> {
+    let args = sign(client, nucleus_id, arg, signer).await?;
+    let payload = hex::encode(args.encode());
+    let params = rpc_params![nucleus_id.to_string(), "backfill_feed", payload];
+    let hex_str: String = client.request("nucleus_post", params).await?;
+    let hex = hex::decode(&hex_str)?;
+    Result::<Option<ContentId>, String>::decode(&mut &hex[..])?.map_err(|e| e.into())
+}
+
 pub async fn get_balances<T: ClientT>(
     client: T,
     nucleus_id: NucleusId,
@@ -253,11 +321,45 @@ pub async fn main() {
         }
         SubCmd::SetKey(cmd) => {
             let client = build_client(&cli.options.get_rpc());
-            match set_openai_key(client, nucleus_id, cmd.key).await {
+            let signer = match cmd.get_signer() {
+                Ok(signer) => signer,
+                Err(e) => return eprintln!("{}", e),
+            };
+            let arg = SetLlmKeyArg {
+                llm_name: cmd.vendor,
+                key: cmd.llm_key,
+            };
+            match set_llm_key(client, nucleus_id, arg, signer).await {
                 Ok(_) => println!("Key set"),
                 Err(e) => eprintln!("{:?}", e),
             }
         }
+        SubCmd::BootstrapAdmins => {
+            let client = build_client(&cli.options.get_rpc());
+            match bootstrap_admins(client, nucleus_id).await {
+                Ok(_) => println!("Admins set"),
+                Err(e) => eprintln!("{:?}", e),
+            }
+        }
+        SubCmd::BackfillFeed(cmd) => {
+            let client = build_client(&cli.options.get_rpc());
+            let signer = match cmd.get_signer() {
+                Ok(signer) => signer,
+                Err(e) => return eprintln!("{}", e),
+            };
+            let mut from = Some(cmd.from);
+            while let Some(f) = from {
+                let arg = BackfillFeedArg {
+                    from: f,
+                    limit: cmd.limit,
+                };
+                match backfill_feed(&client, &nucleus_id, arg, &signer).await {
+                    Ok(next) => from = next,
+                    Err(e) => return eprintln!("{:?}", e),
+                }
+            }
+            println!("Feed indexed");
+        }
         SubCmd::GetBalances(cmd) => {
             let client = build_client(&cli.options.get_rpc());
             match get_balances(client, nucleus_id, cmd.account).await {
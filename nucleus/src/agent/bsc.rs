@@ -8,7 +8,7 @@ use crate::eth_types::{Address, TxHash, U256, U64};
 use ethabi::Token;
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use vemodel::{Community, CommunityId};
+use vemodel::{AccountId, Community, CommunityId};
 use vrs_core_sdk::tss::CryptoType;
 use vrs_core_sdk::{
     http::{self, HttpMethod, HttpRequest, HttpResponse, RequestHead},
@@ -128,6 +128,7 @@ struct ResultData {
 
 #[derive(Clone)]
 pub struct TransactionDetails {
+    pub hash: String,
     pub amount_received: u128,
     pub sender: String,
     pub block_number: u64,
@@ -149,6 +150,7 @@ pub(crate) fn on_checking_bnb_transfer(
                 let block_number = u64::from_str_radix(&tx_data.block_number[2..], 16).unwrap_or(0);
 
                 return Ok(Some(TransactionDetails {
+                    hash: tx_data.hash.clone(),
                     amount_received: amount as u128,
                     sender: tx_data.from.clone(),
                     block_number,
@@ -159,22 +161,87 @@ pub(crate) fn on_checking_bnb_transfer(
     Ok(None)
 }
 
+pub(crate) enum IssueResult {
+    Pending,
+    Reverted,
+    Deployed {
+        fund_contract: String,
+        token_contract: Option<String>,
+    },
+}
+
 pub(crate) fn on_checking_issue_result(
     response: CallResult<HttpResponse>,
-) -> Result<(Option<String>, Option<String>), Box<dyn std::error::Error>> {
+) -> Result<IssueResult, Box<dyn std::error::Error>> {
     let r = response.map_err(|e| e.to_string())?;
     let response: RpcResponse<ResultData> = serde_json::from_slice(&r.body)
         .map_err(|e| format!("unable to deserialize body from BSC rpc: {:?}", e))?;
     if let Some(result_data) = response.result {
+        if result_data.receipt.status == "0x0" {
+            return Ok(IssueResult::Reverted);
+        }
         let logs = result_data.receipt.logs;
-        let first = logs.get(0).map(|l| l.address.clone());
-        let second = logs.get(1).map(|l| l.address.clone());
-        return Ok((first, second));
+        return Ok(match logs.get(0).map(|l| l.address.clone()) {
+            Some(fund_contract) => IssueResult::Deployed {
+                fund_contract,
+                token_contract: logs.get(1).map(|l| l.address.clone()),
+            },
+            None => IssueResult::Pending,
+        });
     }
-    Ok((None, None))
+    Ok(IssueResult::Pending)
+}
+
+pub(crate) enum TxStatus {
+    Pending,
+    Reverted,
+    Confirmed,
+}
+
+pub(crate) fn on_checking_tx_status(
+    response: CallResult<HttpResponse>,
+) -> Result<TxStatus, Box<dyn std::error::Error>> {
+    let r = response.map_err(|e| e.to_string())?;
+    let response: RpcResponse<ResultData> = serde_json::from_slice(&r.body)
+        .map_err(|e| format!("unable to deserialize body from BSC rpc: {:?}", e))?;
+    Ok(match response.result {
+        Some(result_data) if result_data.receipt.status == "0x0" => TxStatus::Reverted,
+        Some(_) => TxStatus::Confirmed,
+        None => TxStatus::Pending,
+    })
+}
+
+fn sign_and_send(community_id: CommunityId, tx: TransactionRequest) -> Result<u64, String> {
+    let tx = TypedTransaction::Legacy(tx);
+    let sign_hash = tx.sighash();
+    let r = vrs_core_sdk::tss::tss_sign(
+        CryptoType::EcdsaSecp256k1,
+        community_id.to_be_bytes(),
+        sign_hash.0,
+    )
+    .map_err(|e| e.to_string())?;
+    let v: u64 = r.last().unwrap().clone() as u64 + BSC_CHAIN_ID * 2 + 35;
+    let signature = Signature {
+        v,
+        r: U256::from_big_endian(&r[0..32]),
+        s: U256::from_big_endian(&r[32..64]),
+    };
+    let signed_tx = tx.rlp_signed(&signature);
+    let raw = format!("0x{}", hex::encode(signed_tx.to_vec()));
+    send_raw_transaction(raw.as_str())
 }
 
-pub fn issue_token(community: &Community, community_id: &CommunityId) -> Result<(), String> {
+pub(crate) fn stored_gas_price() -> Option<u64> {
+    crate::find(&crate::trie::GASPRICE_STORAGE_KEY.to_be_bytes()).unwrap_or_default()
+}
+
+/// query the nonce of the agent account, then send the issuing tx with it
+pub fn issue_token(community: &Community) -> Result<(), String> {
+    let id = query_nonce(&community.agent_pubkey.to_string())?;
+    trace(id, HttpCallType::QueryIssueNonce(community.id())).map_err(|e| e.to_string())
+}
+
+pub(crate) fn send_issue_tx(community: &Community, nonce: u64) -> Result<(), String> {
     let contract_bytecode =
         hex::decode(BYTECODE.trim_start_matches("0x")).expect("invalid bytecode");
     let token = community.token_info.clone();
@@ -188,9 +255,7 @@ pub fn issue_token(community: &Community, community_id: &CommunityId) -> Result<
         Token::Address(contract_address),
     ]);
     let full_bytecode = [contract_bytecode, constructor_args].concat();
-    let gas_price: Option<u64> =
-        crate::find(&crate::trie::GASPRICE_STORAGE_KEY.to_be_bytes()).unwrap_or_default();
-    let gas_price = gas_price.map(|s| U256::from(s));
+    let gas_price = stored_gas_price().map(|s| U256::from(s));
     let addr = community.agent_pubkey.clone();
     let addr = Address::from_slice(addr.0.as_slice());
     let tx = TransactionRequest {
@@ -200,30 +265,83 @@ pub fn issue_token(community: &Community, community_id: &CommunityId) -> Result<
         gas_price,
         value: None,
         data: Some(Bytes::from(full_bytecode)),
-        nonce: Some(U256::from(0)),
+        nonce: Some(U256::from(nonce)),
         chain_id: Some(U64::from(56)),
     };
-    let tx = TypedTransaction::Legacy(tx);
-    let sign_hash = tx.sighash();
-    let r = vrs_core_sdk::tss::tss_sign(
-        CryptoType::EcdsaSecp256k1,
-        community_id.to_be_bytes(),
-        sign_hash.0,
-    )
+    let id = sign_and_send(community.id(), tx)?;
+    trace(id, HttpCallType::SendIssueTx(community.id())).map_err(|e| e.to_string())
+}
+
+pub(crate) fn query_nonce(address: &str) -> Result<u64, String> {
+    query_account(address, "eth_getTransactionCount", "pending")
+}
+
+pub(crate) fn query_balance(address: &str) -> Result<u64, String> {
+    query_account(address, "eth_getBalance", "latest")
+}
+
+fn query_account(address: &str, method: &str, block: &str) -> Result<u64, String> {
+    let mut headers = BTreeMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    let body = serde_json::json!({
+        "jsonrpc": "2.0",
+        "method": method,
+        "params": [address, block],
+        "id": 1,
+    });
+    let response = http::request(HttpRequest {
+        head: RequestHead {
+            method: HttpMethod::Post,
+            uri: BSC_URL.to_string(),
+            headers,
+        },
+        body: serde_json::to_vec(&body).expect("json;qed"),
+    })
     .map_err(|e| e.to_string())?;
-    let v: u64 = r.last().unwrap().clone() as u64 + BSC_CHAIN_ID * 2 + 35;
-    let signature = Signature {
-        v,
-        r: U256::from_big_endian(&r[0..32]),
-        s: U256::from_big_endian(&r[32..64]),
+    Ok(response)
+}
+
+pub(crate) fn on_querying_quantity(
+    response: CallResult<HttpResponse>,
+) -> Result<u128, Box<dyn std::error::Error>> {
+    let response = response.map_err(|e| e.to_string())?;
+    let response: RpcResponse<String> = serde_json::from_slice(&response.body)
+        .map_err(|e| format!("unable to deserialize body from BSC rpc: {:?}", e))?;
+    let quantity = response.result.ok_or("empty result from BSC rpc")?;
+    Ok(u128::from_str_radix(quantity.trim_start_matches("0x"), 16)?)
+}
+
+/// send back the activation fee from the agent account, the gas is paid from the fee
+pub(crate) fn refund(
+    community: &Community,
+    nonce: u64,
+    gas_price: u64,
+    balance: u128,
+    recipient: AccountId,
+    amount: u128,
+) -> Result<(), String> {
+    let gas = 21000u128;
+    let value = amount
+        .min(balance)
+        .checked_sub(gas * gas_price as u128)
+        .filter(|v| *v > 0)
+        .ok_or("the balance is not enough to pay the gas".to_string())?;
+    let tx = TransactionRequest {
+        from: Some(Address::from_slice(community.agent_pubkey.0.as_slice())),
+        to: Some(Address::from_slice(recipient.0.as_slice()).into()),
+        gas: Some(U256::from(gas)),
+        gas_price: Some(U256::from(gas_price)),
+        value: Some(U256::from(value)),
+        data: None,
+        nonce: Some(U256::from(nonce)),
+        chain_id: Some(U64::from(BSC_CHAIN_ID)),
     };
-    let signed_tx = tx.rlp_signed(&signature);
-    let raw = format!("0x{}", hex::encode(signed_tx.to_vec()));
-    let id = send_raw_transaction(raw.as_str()).expect("send raw error");
-    trace(id, HttpCallType::SendIssueTx(community.id()))
-        .map_err(|e| e.to_string())
-        .expect("send Issue tx error");
-    Ok(())
+    let id = sign_and_send(community.id(), tx)?;
+    trace(
+        id,
+        HttpCallType::SendRefundTx(community.id(), nonce, gas_price),
+    )
+    .map_err(|e| e.to_string())
 }
 
 pub(crate) fn on_checking_gas_price(
@@ -260,12 +378,12 @@ pub fn query_gas_price() -> Result<u64, String> {
     Ok(response)
 }
 
-pub(crate) fn on_issuing_tx(
+pub(crate) fn on_sending_tx(
     response: CallResult<HttpResponse>,
 ) -> Result<Option<TxHash>, Box<dyn std::error::Error>> {
     let response = response.map_err(|e| e.to_string())?;
     let resp = String::from_utf8(response.body).map_err(|e| e.to_string())?;
-    vrs_core_sdk::println!("send issue result: {}", &resp);
+    vrs_core_sdk::println!("send tx result: {}", &resp);
     let response: crate::agent::bsc::RpcResponse<String> = serde_json::from_str(&resp)
         .map_err(|e| format!("unable to deserialize body from BSC rpc: {:?}", e))?;
     if let Some(result_data) = response.result {
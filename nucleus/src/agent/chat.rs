@@ -0,0 +1,310 @@
+use super::openai::{ToolCall, Usage};
+use super::tools::{self, ToolContext};
+use super::{HttpCallType, LlmProvider};
+use crate::trie;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use vemodel::{Comment, Community, ContentId, Thread};
+use vrs_core_sdk::http::{self, HttpMethod, HttpRequest, HttpResponse, RequestHead};
+use vrs_core_sdk::CallResult;
+
+const MAX_SESSION_MESSAGES: usize = 40;
+const MAX_TOOL_ROUNDS: u32 = 5;
+
+/// the stateless Chat Completions API of DeepSeek or any OpenAI-compatible host,
+/// the messages of a thread are kept in the storage and sent on every run
+pub(crate) struct ChatCompletions {
+    pub host: String,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct ChatMessage {
+    pub role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatMessage {
+    fn new(role: &str, content: String) -> Self {
+        Self {
+            role: role.to_string(),
+            content: Some(content),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChatCompletionObject {
+    pub id: String,
+    #[serde(default)]
+    pub model: String,
+    pub choices: Vec<ChatChoice>,
+    pub usage: Option<Usage>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ChatChoice {
+    pub index: u32,
+    pub message: ChatMessage,
+    pub finish_reason: Option<String>,
+}
+
+impl LlmProvider for ChatCompletions {
+    fn init_agent(&self, _community: &Community) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn update_agent(&self, _community: &Community, _attempts: u32) -> Result<(), String> {
+        Ok(())
+    }
+
+    fn create_session_and_run(
+        &self,
+        community: &Community,
+        thread: &Thread,
+        text: &str,
+    ) -> Result<(), String> {
+        let images = thread
+            .images
+            .iter()
+            .filter_map(|i| i.public_url(super::IPFS_GATEWAY))
+            .collect::<Vec<_>>();
+        let text_msg = serde_json::json!({
+            "id": thread.id,
+            "title": thread.title,
+            "content": text,
+            "images": images,
+            "author": thread.author,
+            "mention": thread.mention,
+            "created_time": thread.created_time,
+        });
+        let mut session = vec![ChatMessage::new(
+            "user",
+            serde_json::to_string(&text_msg).expect("json;qed"),
+        )];
+        save_session(thread.id(), &mut session)?;
+        self.complete(community, thread.id(), &session, 0)
+    }
+
+    /// the session is sent again, the tool rounds are counted from zero
+    fn retry(&self, community: &Community, content_id: ContentId) -> Result<(), String> {
+        let thread_id = content_id & (u128::MAX - u32::MAX as u128);
+        let session = load_session(thread_id)?;
+        self.complete(community, content_id, &session, 0)
+    }
+
+    fn append_message_then_run(
+        &self,
+        community: &Community,
+        thread: &Thread,
+        comment: &Comment,
+        text: &str,
+    ) -> Result<(), String> {
+        let images = comment
+            .images
+            .iter()
+            .filter_map(|i| i.public_url(super::IPFS_GATEWAY))
+            .collect::<Vec<_>>();
+        let text_msg = serde_json::json!({
+            "id": comment.id,
+            "content": text,
+            "images": images,
+            "author": comment.author,
+            "mention": comment.mention,
+            "created_time": comment.created_time,
+        });
+        let mut session = load_session(thread.id())?;
+        session.push(ChatMessage::new(
+            "user",
+            serde_json::to_string(&text_msg).expect("json;qed"),
+        ));
+        save_session(thread.id(), &mut session)?;
+        self.complete(community, comment.id(), &session, 0)
+    }
+
+    fn on_response(
+        &self,
+        community: &Community,
+        call_type: HttpCallType,
+        response: CallResult<HttpResponse>,
+    ) -> Result<(), String> {
+        let HttpCallType::ChatCompletion(content_id, rounds) = call_type else {
+            return Err("Not a Chat Completions call".to_string());
+        };
+        let thread_id = content_id & (u128::MAX - u32::MAX as u128);
+        if super::is_rate_limited(&response) {
+            return super::retry_or_fail(community, content_id, "rate limited");
+        }
+        let completion = match super::parse_response::<ChatCompletionObject>(response) {
+            Ok(completion) => completion,
+            Err(e) => return super::fail_invocation(community, content_id, &e),
+        };
+        if let Some(usage) = &completion.usage {
+            crate::usage::record(
+                community,
+                &completion.model,
+                usage.prompt_tokens,
+                usage.completion_tokens,
+            )?;
+        }
+        let message = match completion.choices.into_iter().next() {
+            Some(choice) => choice.message,
+            None => return super::fail_invocation(community, content_id, "no choice"),
+        };
+        let mut session = load_session(thread_id)?;
+        match message.tool_calls.clone().filter(|calls| !calls.is_empty()) {
+            Some(_) if super::is_overdue(&super::get_invocation(content_id)?) => {
+                super::fail_invocation(community, content_id, "timeout")
+            }
+            Some(_) if crate::usage::is_over_budget(community)? => {
+                super::pause_invocation(community, content_id)
+            }
+            Some(calls) if rounds < MAX_TOOL_ROUNDS => {
+                session.push(message);
+                let ctx = ToolContext {
+                    community,
+                    run_id: &completion.id,
+                    authors: super::run_authors(content_id)?,
+                };
+                for call in calls {
+                    let output = tools::call(&ctx, &call.function.name, &call.function.arguments)
+                        .unwrap_or_else(|e| e);
+                    session.push(ChatMessage {
+                        role: "tool".to_string(),
+                        content: Some(output),
+                        tool_call_id: Some(call.id),
+                        ..Default::default()
+                    });
+                }
+                save_session(thread_id, &mut session)?;
+                self.complete(community, content_id, &session, rounds + 1)
+            }
+            _ => {
+                let text = message.content.unwrap_or_default();
+                session.push(ChatMessage::new("assistant", text.clone()));
+                save_session(thread_id, &mut session)?;
+                if text.trim().is_empty() {
+                    return super::fail_invocation(community, content_id, "empty reply");
+                }
+                super::finish_invocation(content_id)?;
+                super::reply(community, content_id, &text)
+            }
+        }
+    }
+}
+
+impl ChatCompletions {
+    /// the prompt is prepended on every run so that it could be updated
+    fn complete(
+        &self,
+        community: &Community,
+        content_id: ContentId,
+        session: &[ChatMessage],
+        rounds: u32,
+    ) -> Result<(), String> {
+        let prompt = super::decorate_prompt(
+            &community.name,
+            &community.agent_pubkey,
+            &community.prompt,
+            &community.token_info,
+        );
+        let mut messages = vec![ChatMessage::new("system", prompt)];
+        messages.extend_from_slice(session);
+        let mut headers = BTreeMap::new();
+        headers.insert("Content-Type".to_string(), "application/json".to_string());
+        headers.insert(
+            "Authorization".to_string(),
+            format!("Bearer {}", community.llm_vendor.key()),
+        );
+        let mut body = serde_json::json!({
+            "messages": messages,
+        });
+        let definitions = tools::definitions(community);
+        if definitions
+            .as_array()
+            .map(|t| !t.is_empty())
+            .unwrap_or_default()
+        {
+            body["tools"] = definitions;
+        }
+        super::apply_generation(&mut body, community, "max_tokens");
+        let id = http::request(HttpRequest {
+            head: RequestHead {
+                method: HttpMethod::Post,
+                uri: format!("{}/chat/completions", self.host.trim_end_matches('/')),
+                headers,
+            },
+            body: serde_json::to_vec(&body).expect("json;qed"),
+        })
+        .map_err(|e| e.to_string())?;
+        super::trace(id, HttpCallType::ChatCompletion(content_id, rounds))
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// a single completion without the session and tools
+pub(crate) fn complete_once(
+    community: &Community,
+    instruction: String,
+    call_type: HttpCallType,
+) -> Result<(), String> {
+    let prompt = super::decorate_prompt(
+        &community.name,
+        &community.agent_pubkey,
+        &community.prompt,
+        &community.token_info,
+    );
+    let messages = vec![
+        ChatMessage::new("system", prompt),
+        ChatMessage::new("user", instruction),
+    ];
+    let mut headers = BTreeMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers.insert(
+        "Authorization".to_string(),
+        format!("Bearer {}", community.llm_vendor.key()),
+    );
+    let mut body = serde_json::json!({
+        "messages": messages,
+    });
+    super::apply_generation(&mut body, community, "max_tokens");
+    let id = http::request(HttpRequest {
+        head: RequestHead {
+            method: HttpMethod::Post,
+            uri: format!(
+                "{}/chat/completions",
+                community.llm_vendor.host().trim_end_matches('/')
+            ),
+            headers,
+        },
+        body: serde_json::to_vec(&body).expect("json;qed"),
+    })
+    .map_err(|e| e.to_string())?;
+    super::trace(id, call_type).map_err(|e| e.to_string())
+}
+
+fn load_session(thread_id: ContentId) -> Result<Vec<ChatMessage>, String> {
+    match crate::find::<Vec<u8>>(&trie::to_chat_session_key(thread_id))? {
+        Some(v) => serde_json::from_slice(&v).map_err(|e| e.to_string()),
+        None => Ok(vec![]),
+    }
+}
+
+/// keep the latest messages, the session should always start with a user message
+fn save_session(thread_id: ContentId, session: &mut Vec<ChatMessage>) -> Result<(), String> {
+    if session.len() > MAX_SESSION_MESSAGES {
+        session.drain(..session.len() - MAX_SESSION_MESSAGES);
+    }
+    let start = session
+        .iter()
+        .position(|m| m.role == "user")
+        .unwrap_or(session.len());
+    session.drain(..start);
+    let v = serde_json::to_vec(session).expect("json;qed");
+    crate::save(&trie::to_chat_session_key(thread_id), &v)
+}
@@ -0,0 +1,174 @@
+use super::chat::{self, ChatCompletionObject};
+use super::{tools, HttpCallType};
+use crate::trie;
+use std::time::Duration;
+use vemodel::{args::AddAgentJobArg, *};
+use vrs_core_sdk::{
+    codec::{Decode, Encode},
+    http::HttpResponse,
+    set_timer, storage, timer, CallResult,
+};
+
+const MAX_JOBS: usize = 5;
+const MAX_DIGEST_THREADS: usize = 20;
+
+pub(crate) fn get_jobs(community_id: CommunityId) -> Result<Vec<AgentJob>, String> {
+    let start_key = trie::to_agent_job_key(community_id, 0);
+    let result = storage::get_range(&start_key, storage::Direction::Forward, MAX_JOBS)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, v) in result.into_iter() {
+        if k.len() == 16 && k.starts_with(&start_key[..12]) {
+            r.push(AgentJob::decode(&mut &v[..]).map_err(|e| e.to_string())?);
+        }
+    }
+    Ok(r)
+}
+
+fn allocate_job_id(community_id: CommunityId) -> Result<u32, String> {
+    let end_key = trie::to_agent_job_key(community_id, u32::MAX);
+    let id = storage::search(&end_key, storage::Direction::Reverse)
+        .map_err(|e| e.to_string())?
+        .filter(|(k, _)| k.len() == 16 && k.starts_with(&end_key[..12]))
+        .map(|(k, _)| u32::from_be_bytes(k[12..].try_into().expect("qed")) + 1)
+        .unwrap_or(0);
+    Ok(id)
+}
+
+fn schedule(community_id: CommunityId, job: &AgentJob) -> Result<(), String> {
+    set_timer!(
+        Duration::from_secs(job.next_run.saturating_sub(timer::now())),
+        run_agent_job,
+        community_id,
+        job.id,
+        job.next_run
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+pub(crate) fn add_job(community_id: CommunityId, arg: AddAgentJobArg) -> Result<u32, String> {
+    (get_jobs(community_id)?.len() < MAX_JOBS)
+        .then(|| ())
+        .ok_or(format!("No more than {} jobs in a community", MAX_JOBS))?;
+    let now = timer::now();
+    let job = AgentJob {
+        id: allocate_job_id(community_id)?,
+        title: arg.title.trim().to_string(),
+        instruction: arg.instruction,
+        interval: arg.interval,
+        next_run: arg
+            .start_at
+            .filter(|t| *t > now)
+            .unwrap_or(now + arg.interval),
+    };
+    schedule(community_id, &job)?;
+    crate::save(&trie::to_agent_job_key(community_id, job.id), &job)?;
+    Ok(job.id)
+}
+
+/// the scheduled timer stops once the job is removed
+pub(crate) fn remove_job(community_id: CommunityId, job_id: u32) -> Result<(), String> {
+    let key = trie::to_agent_job_key(community_id, job_id);
+    crate::find::<AgentJob>(&key)?.ok_or("Job not found".to_string())?;
+    storage::del(&key).map_err(|e| e.to_string())
+}
+
+/// the timers of the removed or rescheduled jobs are ignored
+#[timer]
+pub(crate) fn run_agent_job(
+    community_id: CommunityId,
+    job_id: u32,
+    scheduled: u64,
+) -> Result<(), String> {
+    let key = trie::to_agent_job_key(community_id, job_id);
+    let mut job = match crate::find::<AgentJob>(&key)? {
+        Some(job) if job.next_run == scheduled => job,
+        _ => return Ok(()),
+    };
+    // skip the missed runs
+    let now = timer::now();
+    if job.next_run <= now {
+        job.next_run += ((now - job.next_run) / job.interval + 1) * job.interval;
+    }
+    crate::save(&key, &job)?;
+    schedule(community_id, &job)?;
+    let community = crate::try_find_community(community_id)?;
+    if crate::ensure_active(&community).is_err() || crate::usage::is_over_budget(&community)? {
+        vrs_core_sdk::println!("the job {} of {} is skipped", job_id, community.name);
+        return Ok(());
+    }
+    let threads = tools::community_threads(community_id, MAX_DIGEST_THREADS)?
+        .iter()
+        .map(|t| {
+            let mut summary = tools::thread_summary(t);
+            summary["content"] = tools::excerpt(&community, &t.content).into();
+            summary
+        })
+        .collect::<Vec<_>>();
+    let threads = serde_json::to_string(&threads).map_err(|e| e.to_string())?;
+    let instruction = format!(
+        "Write a new thread of the community following the instruction below, \
+        reply with the content only.\n\nInstruction: {}\n\nRecent threads: {}",
+        job.instruction,
+        tools::bounded(threads, tools::MAX_OUTPUT_LEN),
+    );
+    chat::complete_once(
+        &community,
+        instruction,
+        HttpCallType::RunningJob(community_id, job_id),
+    )
+}
+
+pub(crate) fn on_response(
+    community_id: CommunityId,
+    job_id: u32,
+    response: CallResult<HttpResponse>,
+) -> Result<(), String> {
+    let community = crate::try_find_community(community_id)?;
+    let job = crate::find::<AgentJob>(&trie::to_agent_job_key(community_id, job_id))?
+        .ok_or("Job not found".to_string())?;
+    let completion = super::parse_response::<ChatCompletionObject>(response)?;
+    if let Some(usage) = &completion.usage {
+        crate::usage::record(
+            &community,
+            &completion.model,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+        )?;
+    }
+    let text = completion
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .filter(|t| !t.trim().is_empty())
+        .ok_or("Empty job output".to_string())?;
+    publish_thread(&community, &job.title, &text)
+}
+
+/// the thread is authored by the agent and not replied by itself
+fn publish_thread(community: &Community, title: &str, text: &str) -> Result<(), String> {
+    let id = crate::allocate_thread_id(community.id())?;
+    let thread = Thread {
+        id: hex::encode(id.encode()),
+        community_name: community.name.clone(),
+        title: title.to_string(),
+        content: crate::compress(text)?,
+        legacy_images: vec![],
+        author: community.agent_pubkey,
+        mention: vec![],
+        llm_session_id: Default::default(),
+        created_time: timer::now() as i64,
+        images: vec![],
+        locked: false,
+        pinned: false,
+        tags: vec![],
+        summary: None,
+        summarized_at: None,
+        accepted_answer: None,
+    };
+    crate::save(&trie::to_content_key(id), &thread)?;
+    crate::feed::index_thread(&thread)?;
+    crate::save_event(Event::ThreadPosted(id))
+}
@@ -1,11 +1,16 @@
 pub(crate) mod bsc;
+pub(crate) mod chat;
 pub mod contract;
+pub(crate) mod jobs;
 pub(crate) mod openai;
 pub mod rewards;
+pub(crate) mod summary;
+pub(crate) mod tools;
+pub(crate) mod treasury;
 // pub(crate) mod solana;
 
 use crate::trie::{to_community_key, to_invitecode_amt_key};
-use crate::{find, get_account_info, save, trie, try_find_community, MIN_ACTIVATE_FEE};
+use crate::{find, get_account_info, save, trie, try_find_community};
 use const_hex::ToHexExt;
 use serde::de::DeserializeOwned;
 use std::str::FromStr;
@@ -17,7 +22,41 @@ use vrs_core_sdk::{
 pub const OPENAI: [u8; 4] = *b"opai";
 pub const DEEPSEEK: [u8; 4] = *b"dpsk";
 
-pub const DEEPSEEK_API_HOST: &'static str = "https://api.deepseek.ai";
+pub const IPFS_GATEWAY: &str = "https://ipfs.io";
+pub const OPENAI_MODEL: &str = "gpt-4o";
+pub const DEEPSEEK_MODEL: &str = "deepseek-chat";
+
+pub const OPENAI_API_HOST: &str = "https://api.openai.com/v1";
+pub const DEEPSEEK_API_HOST: &str = "https://api.deepseek.com";
+
+pub(crate) fn is_platform_host(vendor: &LlmVendor) -> bool {
+    match vendor {
+        LlmVendor::OpenAI { host, .. } => host == OPENAI_API_HOST,
+        LlmVendor::DeepSeek { host, .. } => host == DEEPSEEK_API_HOST,
+    }
+}
+
+pub(crate) fn model_of(vendor: &LlmVendor, generation: &GenerationParams) -> String {
+    generation.model.clone().unwrap_or_else(|| match vendor {
+        LlmVendor::OpenAI { .. } => OPENAI_MODEL.to_string(),
+        LlmVendor::DeepSeek { .. } => DEEPSEEK_MODEL.to_string(),
+    })
+}
+
+/// fill the generation parameters into the request body
+fn apply_generation(body: &mut serde_json::Value, community: &Community, max_tokens_field: &str) {
+    let generation = &community.generation;
+    body["model"] = model_of(&community.llm_vendor, generation).into();
+    if let Some(temperature) = generation.temperature {
+        body["temperature"] = (temperature as f64 / 1000.0).into();
+    }
+    if let Some(top_p) = generation.top_p {
+        body["top_p"] = (top_p as f64 / 1000.0).into();
+    }
+    if let Some(max_tokens) = generation.max_tokens {
+        body[max_tokens_field] = max_tokens.into();
+    }
+}
 
 pub(crate) fn set_sys_key(vendor: [u8; 4], key: String) -> Result<(), String> {
     let ty = crate::trie::llm_key(vendor);
@@ -68,6 +107,83 @@ pub enum HttpCallType {
     QueryIssueResult(CommunityId, String),
     CheckingInviteTx(CommunityId),
     CheckingPayToJoinTx(CommunityId),
+    QueryRefundNonce(CommunityId),
+    QueryRefundBalance(CommunityId, u64),
+    SendRefundTx(CommunityId, u64, u64),
+    ChatCompletion(ContentId, u32),
+    UpdatingAgent(CommunityId),
+    CancellingRun(ContentId),
+    RunningJob(CommunityId, u32),
+    SummarizingThread(ContentId, u64),
+    CheckingIssueTx(CommunityId, String, u32),
+    QueryIssueNonce(CommunityId),
+    CheckingRefundTx(CommunityId, String, u32),
+    SyncingAgent(CommunityId, u32),
+}
+
+impl HttpCallType {
+    /// the community of a LLM call
+    fn llm_community(&self) -> Option<CommunityId> {
+        match self {
+            Self::CreatingAgent(community_id)
+            | Self::UpdatingAgent(community_id)
+            | Self::SyncingAgent(community_id, _) => Some(*community_id),
+            Self::AppendingMessage(content_id)
+            | Self::InvokingLLM(content_id)
+            | Self::CheckInvocationStatus(content_id)
+            | Self::PullingMessage(content_id)
+            | Self::SubmittingToolCall(content_id)
+            | Self::CancellingRun(content_id)
+            | Self::ChatCompletion(content_id, _) => Some(vemodel::get_belongs_to(*content_id)),
+            _ => None,
+        }
+    }
+}
+
+/// the backends of agents, the responses are dispatched back by `untrace`
+pub(crate) trait LlmProvider {
+    /// prepare the agent after the community is activated
+    fn init_agent(&self, community: &Community) -> Result<(), String>;
+
+    /// apply the changed prompt, model or tools, `attempts` counts the retries
+    fn update_agent(&self, community: &Community, attempts: u32) -> Result<(), String>;
+
+    /// start a session with a new thread and reply to it
+    fn create_session_and_run(
+        &self,
+        community: &Community,
+        thread: &Thread,
+        text: &str,
+    ) -> Result<(), String>;
+
+    /// resume the invocation after it was rate limited
+    fn retry(&self, community: &Community, content_id: ContentId) -> Result<(), String>;
+
+    /// continue the session of the thread and reply to the comment
+    fn append_message_then_run(
+        &self,
+        community: &Community,
+        thread: &Thread,
+        comment: &Comment,
+        text: &str,
+    ) -> Result<(), String>;
+
+    fn on_response(
+        &self,
+        community: &Community,
+        call_type: HttpCallType,
+        response: CallResult<HttpResponse>,
+    ) -> Result<(), String>;
+}
+
+pub(crate) fn provider_of(community: &Community) -> Box<dyn LlmProvider> {
+    match &community.llm_vendor {
+        // the self-hosted servers usually implement the Chat Completions only
+        LlmVendor::OpenAI { host, .. } if host == OPENAI_API_HOST => Box::new(openai::Assistants),
+        LlmVendor::OpenAI { host, .. } | LlmVendor::DeepSeek { host, .. } => {
+            Box::new(chat::ChatCompletions { host: host.clone() })
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -76,8 +192,27 @@ pub enum InvocationStatus {
     WaitingFunctionCall,
     Failed,
     Completed,
+    Incomplete,
+    Expired,
+    Cancelled,
 }
 
+/// the progress of answering a thread or comment, removed once replied
+#[derive(Clone, Debug, Default, Encode, Decode)]
+pub struct Invocation {
+    pub session_id: String,
+    pub run_id: String,
+    pub started_at: u64,
+    pub polls: u32,
+    pub retries: u32,
+}
+
+const MAX_INVOCATION_SECS: u64 = 600;
+const MAX_INVOCATION_RETRIES: u32 = 5;
+const CANNOT_ANSWER: &str = "Sorry, I could not answer this right now. Please try again later.";
+const PAUSED_BY_BUDGET: &str =
+    "Sorry, the agent of this community is paused since its budget is used up.";
+
 #[callback]
 pub fn on_response(id: u64, response: CallResult<HttpResponse>) {
     let key = crate::trie::http_trace_key(id);
@@ -125,16 +260,27 @@ fn untrace(
             match bsc::on_checking_bnb_transfer(&agent_addr, response).map_err(|e| e.to_string()) {
                 Ok(Some(tx)) => match community.status.clone() {
                     CommunityStatus::WaitingTx(min_fee) => {
-                        if tx.amount_received >= min_fee {
-                            bsc::issue_token(&community, &community_id)?;
-                            community.status = CommunityStatus::PendingCreation;
+                        let payer = AccountId::from_str(&tx.sender)?;
+                        // the same tx could be submitted again after a refund
+                        if tx.amount_received >= min_fee && consume_tx(community_id, &tx)? {
+                            let payment = ActivationPayment {
+                                payer,
+                                amount: tx.amount_received,
+                                issue_tx: None,
+                                refunding: false,
+                                refund_tx: None,
+                                refund_nonce: None,
+                                refund_gas_price: None,
+                            };
+                            crate::save(&trie::to_activation_key(community_id), &payment)?;
+                            community.status = match bsc::issue_token(&community) {
+                                Ok(()) => CommunityStatus::PendingCreation,
+                                Err(e) => {
+                                    CommunityStatus::CreateFailed(format!("Issuing failed: {}", e))
+                                }
+                            };
                             crate::save(&trie::to_community_key(community_id), &community)?;
-                            let mut account = get_account_info(community.creator)?;
-                            account.last_transfer_block = tx.block_number;
-                            crate::save(
-                                &trie::to_account_key(community.creator),
-                                &AccountData::Pubkey(account),
-                            )?;
+                            crate::save_event(Event::CommunityUpdated(community_id))?;
                         }
                     }
                     _ => {}
@@ -150,193 +296,171 @@ fn untrace(
                 .map_err(|e| e.to_string())?
                 .ok_or("No tx found".to_string())?;
             if let CommunityMode::PayToJoin(fee) = community.mode {
-                if tx.amount_received >= fee {
+                if tx.amount_received >= fee && consume_tx(community_id, &tx)? {
                     let account_id = H160::from_str(&tx.sender)?;
-                    let mut account = get_account_info(account_id)?;
-                    if account.last_transfer_block < tx.block_number {
-                        account.last_transfer_block = tx.block_number;
-                        crate::save(
-                            &trie::to_account_key(account.address),
-                            &AccountData::Pubkey(account),
-                        )?;
-                        let permission_key = trie::to_permission_key(community_id, account_id);
-                        let _ = crate::save(permission_key.as_ref(), &1u32);
-                        let amount: u64 = tx.amount_received.try_into().unwrap();
-                        let creator_share = amount * 7 / 10;
-                        let platform_share = amount - creator_share;
-                        community.creator_bnb_benefit += creator_share;
-                        community.platform_bnb_benefit += platform_share;
-                        let key = trie::to_community_key(community_id);
-                        crate::save(&key, &community)?;
-                    }
+                    let permission_key = trie::to_permission_key(community_id, account_id);
+                    let _ = crate::save(permission_key.as_ref(), &1u32);
+                    let amount: u64 = tx.amount_received.try_into().unwrap();
+                    let (creator_share, platform_share) =
+                        crate::get_platform_config()?.split(amount);
+                    community.creator_bnb_benefit += creator_share;
+                    community.platform_bnb_benefit += platform_share;
+                    let key = trie::to_community_key(community_id);
+                    crate::save(&key, &community)?;
                 }
             }
         }
-        HttpCallType::SendIssueTx(community_id) => match bsc::on_issuing_tx(response) {
+        HttpCallType::SendIssueTx(community_id) => match bsc::on_sending_tx(response) {
             Ok(Some(tx)) => {
+                let tx = tx.encode_hex_with_prefix();
                 let mut community = try_find_community(community_id)?;
-                community.status = CommunityStatus::TokenIssued(tx.to_string());
+                let mut payment = find_activation_payment(community_id)?;
+                payment.issue_tx = Some(tx.clone());
+                crate::save(&trie::to_activation_key(community_id), &payment)?;
+                community.status = CommunityStatus::TokenIssued(tx.clone());
                 crate::save(&trie::to_community_key(community_id), &community)?;
-                let _ = set_timer!(
-                    std::time::Duration::from_secs(5),
-                    check_issue_token_tx,
-                    community_id,
-                    tx.encode_hex_with_prefix()
-                );
+                let _ = set_timer!(tx_query_backoff(0), check_issue_tx, community_id, tx, 0u32);
+            }
+            Ok(None) => {
+                fail_activation(community_id, "The issuing tx is rejected".to_string())?;
             }
-            _ => {
-                let mut community = crate::try_find_community(community_id)?;
-                community.status = CommunityStatus::WaitingTx(MIN_ACTIVATE_FEE);
-                crate::save(&to_community_key(community_id), &community)?;
+            Err(e) => {
+                fail_activation(community_id, format!("Sending issuing tx failed: {}", e))?;
             }
         },
-        HttpCallType::QueryIssueResult(community_id, tx) => {
-            match bsc::on_checking_issue_result(response) {
-                Ok((Some(fund_contract), token_contract)) => {
-                    let mut community = crate::try_find_community(community_id)?;
-                    let contract_addr = AccountId::from_str(fund_contract.as_str())?;
-                    community.agent_contract = Some(contract_addr);
-                    if community.token_info.new_issue {
-                        community.token_info.contract = token_contract
-                            .map(|c| AccountId::from_str(c.as_str()).unwrap())
-                            .ok_or("The tx should include a token contract".to_string())?;
-                        storage::put(
-                            &crate::trie::to_balance_key(community_id, community.agent_pubkey),
-                            community.token_info.total_issuance.encode(),
-                        )
-                        .map_err(|e| e.to_string())?;
-                    }
-                    crate::agent::init_agent(&community)?;
-                    community.status = CommunityStatus::Active;
-                    let community_key = to_community_key(community.id());
-                    crate::save(&community_key, &community)?;
-                    crate::save_event(Event::CommunityUpdated(community.id()))?;
-                }
-                _ => {
-                    let _ = set_timer!(
-                        std::time::Duration::from_secs(5),
-                        check_issue_token_tx,
-                        community_id,
-                        tx,
-                    );
-                }
+        HttpCallType::QueryIssueNonce(community_id) => {
+            let result = bsc::on_querying_quantity(response)
+                .map_err(|e| e.to_string())
+                .and_then(|nonce| {
+                    let community = try_find_community(community_id)?;
+                    bsc::send_issue_tx(&community, nonce as u64)
+                });
+            if let Err(e) = result {
+                fail_activation(community_id, format!("Issuing failed: {}", e))?;
             }
         }
-        HttpCallType::CreatingAgent(community_id) => {
-            let assistant_id = openai::resolve_assistant_id(response)?;
-            let mut community = crate::try_find_community(community_id)?;
-            community.llm_assistant_id = assistant_id;
-            crate::save(&trie::to_community_key(community_id), &community)?;
+        // traced before the queries were capped
+        HttpCallType::QueryIssueResult(community_id, tx) => {
+            on_issue_result(community_id, tx, 0, response)?;
         }
-        HttpCallType::AppendingMessage(content_id) => {
-            run(content_id)?;
+        HttpCallType::CheckingIssueTx(community_id, tx, attempts) => {
+            on_issue_result(community_id, tx, attempts, response)?;
         }
-        HttpCallType::InvokingLLM(content_id)
-        | HttpCallType::CheckInvocationStatus(content_id)
-        | HttpCallType::SubmittingToolCall(content_id) => {
-            // TODO define the invocation object to replace the openai::RunObject
-            let run = parse_response::<openai::RunObject>(response)?;
-            let status = match run.status.as_str() {
-                "queued" | "in_progress" => InvocationStatus::Running,
-                "completed" => InvocationStatus::Completed,
-                "requires_action" => InvocationStatus::WaitingFunctionCall,
-                _ => InvocationStatus::Failed,
-            };
-            if vemodel::is_thread(content_id) {
-                let mut thread = crate::find::<Thread>(&crate::trie::to_content_key(content_id))?
-                    .ok_or("Thread not found".to_string())?;
-                if thread.llm_session_id.is_empty() {
-                    thread.llm_session_id = run.thread_id.clone();
-                    crate::save(&crate::trie::to_content_key(content_id), &thread)?;
-                }
+        HttpCallType::QueryRefundNonce(community_id) => {
+            let result = bsc::on_querying_quantity(response)
+                .map_err(|e| e.to_string())
+                .and_then(|nonce| {
+                    let community = try_find_community(community_id)?;
+                    let id = bsc::query_balance(&community.agent_pubkey.to_string())?;
+                    trace(
+                        id,
+                        HttpCallType::QueryRefundBalance(community_id, nonce as u64),
+                    )
+                    .map_err(|e| e.to_string())
+                });
+            if let Err(e) = result {
+                abort_refund(community_id, e)?;
             }
-            let community_id = vemodel::get_belongs_to(content_id);
-            let community = crate::try_find_community(community_id)?;
-            match status {
-                InvocationStatus::Running => {
-                    let _ = set_timer!(
-                        std::time::Duration::from_secs(5),
-                        check_invocation_status,
-                        content_id,
-                        run.thread_id.clone(),
-                        run.id.clone(),
-                    );
+        }
+        HttpCallType::QueryRefundBalance(community_id, nonce) => {
+            let result = bsc::on_querying_quantity(response)
+                .map_err(|e| e.to_string())
+                .and_then(|balance| {
+                    let community = try_find_community(community_id)?;
+                    let payment = find_activation_payment(community_id)?;
+                    let gas_price =
+                        bsc::stored_gas_price().ok_or("gas price is unknown".to_string())?;
+                    // a replacement is accepted only with a higher gas price
+                    let gas_price = match (&payment.refund_tx, payment.refund_gas_price) {
+                        (Some(_), Some(last)) => gas_price.max(last + last / 5),
+                        _ => gas_price,
+                    };
+                    bsc::refund(
+                        &community,
+                        nonce,
+                        gas_price,
+                        balance,
+                        payment.payer,
+                        payment.amount,
+                    )
+                });
+            if let Err(e) = result {
+                abort_refund(community_id, e)?;
+            }
+        }
+        HttpCallType::SendRefundTx(community_id, nonce, gas_price) => {
+            match bsc::on_sending_tx(response) {
+                Ok(Some(tx)) => {
+                    let tx = tx.encode_hex_with_prefix();
+                    let mut payment = find_activation_payment(community_id)?;
+                    payment.refund_tx = Some(tx.clone());
+                    payment.refund_nonce = Some(nonce);
+                    payment.refund_gas_price = Some(gas_price);
+                    crate::save(&trie::to_activation_key(community_id), &payment)?;
+                    let _ =
+                        set_timer!(tx_query_backoff(0), check_refund_tx, community_id, tx, 0u32);
                 }
-                InvocationStatus::WaitingFunctionCall => {
-                    if let Some(actions) = run.required_action {
-                        let call_result = actions
-                            .submit_tool_outputs
-                            .tool_calls
-                            .into_iter()
-                            .map(|call| {
-                                (
-                                    call.id.clone(),
-                                    match call_tool(
-                                        &community,
-                                        &call.function.name,
-                                        &call.function.arguments,
-                                    ) {
-                                        Ok(result) => result,
-                                        Err(e) => e,
-                                    },
-                                )
-                            })
-                            .collect::<Vec<(String, String)>>();
-                        submit_tool_call(
-                            community.llm_vendor.key(),
-                            content_id,
-                            &run.thread_id,
-                            &run.id,
-                            call_result,
-                        )?;
-                    }
+                Ok(None) => on_refund_rejected(community_id, "The refund tx is rejected".into())?,
+                Err(e) => on_refund_rejected(community_id, e.to_string())?,
+            }
+        }
+        HttpCallType::CheckingRefundTx(community_id, tx, attempts) => {
+            match bsc::on_checking_tx_status(response) {
+                Ok(bsc::TxStatus::Confirmed) => {
+                    let mut payment = find_activation_payment(community_id)?;
+                    payment.refunding = false;
+                    crate::save(&trie::to_activation_key(community_id), &payment)?;
+                    // the creator could activate the community again
+                    let mut community = try_find_community(community_id)?;
+                    community.status =
+                        CommunityStatus::WaitingTx(crate::get_platform_config()?.activate_fee);
+                    crate::save(&trie::to_community_key(community_id), &community)?;
+                    crate::save_event(Event::CommunityUpdated(community_id))?;
                 }
-                InvocationStatus::Completed => {
-                    pull_messages(
-                        community.llm_vendor.key(),
-                        content_id,
-                        &run.thread_id,
-                        &run.id,
-                    )?;
+                Ok(bsc::TxStatus::Reverted) => {
+                    let mut payment = find_activation_payment(community_id)?;
+                    payment.refund_tx = None;
+                    crate::save(&trie::to_activation_key(community_id), &payment)?;
+                    abort_refund(community_id, "The refund tx reverted".to_string())?;
                 }
-                InvocationStatus::Failed => {
-                    vrs_core_sdk::println!("{:?}", serde_json::to_string(&run))
+                _ if attempts + 1 < MAX_TX_QUERIES => {
+                    let _ = set_timer!(
+                        tx_query_backoff(attempts + 1),
+                        check_refund_tx,
+                        community_id,
+                        tx,
+                        attempts + 1,
+                    );
                 }
+                // the tx is kept to be replaced by `refund_activation`
+                _ => abort_refund(
+                    community_id,
+                    format!(
+                        "The refund tx {} is not confirmed, refund again to replace it",
+                        tx
+                    ),
+                )?,
             }
         }
-        HttpCallType::PullingMessage(content_id) => {
-            // TODO define the message object to replace the openai::MessageObject
-            let messages = openai::resolve_messages(response)?;
-            let reply = messages
-                .data
-                .into_iter()
-                .find(|m| m.role == openai::MessageRole::assistant);
-            if let Some(reply) = reply {
-                let content = reply
-                    .content
-                    .into_iter()
-                    .filter(|c| c.content_type == "text")
-                    .map(|c| c.text.value)
-                    .collect::<Vec<String>>()
-                    .join("\n");
-                let id = crate::allocate_comment_id(content_id)?;
-                let community_id = (id >> 64) as CommunityId;
-                let community = crate::try_find_community(community_id)?;
-                let key = crate::trie::to_content_key(id);
-                let reply_to =
-                    crate::trie::is_comment(content_id).then(|| hex::encode(content_id.encode()));
-                let comment = Comment {
-                    id: hex::encode(id.encode()),
-                    content: crate::compress(content.as_ref())?,
-                    images: vec![],
-                    author: community.agent_pubkey,
-                    mention: vec![],
-                    reply_to,
-                    created_time: timer::now() as i64,
-                };
-                crate::save(&key, &comment)?;
-                crate::save_event(Event::CommentPosted(id))?;
-            }
+        call_type @ (HttpCallType::CreatingAgent(_)
+        | HttpCallType::AppendingMessage(_)
+        | HttpCallType::InvokingLLM(_)
+        | HttpCallType::CheckInvocationStatus(_)
+        | HttpCallType::PullingMessage(_)
+        | HttpCallType::SubmittingToolCall(_)
+        | HttpCallType::ChatCompletion(..)
+        | HttpCallType::UpdatingAgent(_)
+        | HttpCallType::SyncingAgent(..)
+        | HttpCallType::CancellingRun(_)) => {
+            let community_id = call_type.llm_community().expect("llm call;qed");
+            let community = crate::try_find_community(community_id)?;
+            provider_of(&community).on_response(&community, call_type, response)?;
+        }
+        HttpCallType::RunningJob(community_id, job_id) => {
+            jobs::on_response(community_id, job_id, response)?;
+        }
+        HttpCallType::SummarizingThread(thread_id, requested_at) => {
+            summary::on_response(thread_id, requested_at, response)?;
         }
         HttpCallType::CheckingInviteTx(community_id) => {
             let community = crate::try_find_community(community_id)?;
@@ -344,22 +468,16 @@ fn untrace(
             match bsc::on_checking_bnb_transfer(&agent_addr, response).map_err(|e| e.to_string()) {
                 Ok(Some(tx)) => {
                     let sender = AccountId::from_str(tx.sender.as_str())?;
-                    if sender == community.creator {
-                        let mut account = get_account_info(sender.clone())?;
-                        if account.last_transfer_block < tx.block_number {
-                            account.last_transfer_block = tx.block_number;
-                            if let CommunityMode::InviteOnly = community.mode {
-                                let key = trie::to_account_key(sender);
-                                storage::put(&key, AccountData::Pubkey(account).encode())
-                                    .map_err(|e| e.to_string())?;
-                                let increased = (tx.amount_received / MIN_ACTIVATE_FEE) as u64;
-                                let invite_amount_key = to_invitecode_amt_key(community_id, sender);
-                                let tickets = find::<u64>(invite_amount_key.as_ref())?
-                                    .unwrap_or_default()
-                                    + increased;
-                                save(invite_amount_key.as_ref(), &tickets)?;
-                            }
-                        };
+                    if sender == community.creator
+                        && matches!(community.mode, CommunityMode::InviteOnly)
+                        && consume_tx(community_id, &tx)?
+                    {
+                        let invite_fee = crate::get_platform_config()?.invite_fee;
+                        let increased = (tx.amount_received / invite_fee) as u64;
+                        let invite_amount_key = to_invitecode_amt_key(community_id, sender);
+                        let tickets = find::<u64>(invite_amount_key.as_ref())?.unwrap_or_default()
+                            + increased;
+                        save(invite_amount_key.as_ref(), &tickets)?;
                     }
                 }
                 _ => {
@@ -371,6 +489,23 @@ fn untrace(
     Ok(())
 }
 
+fn schedule_invocation_check(
+    content_id: ContentId,
+    session_id: String,
+    invoke_id: String,
+    polls: u32,
+) -> Result<(), String> {
+    set_timer!(
+        poll_backoff(polls),
+        check_invocation_status,
+        content_id,
+        session_id,
+        invoke_id,
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
 #[timer]
 pub(crate) fn check_invocation_status(
     content_id: ContentId,
@@ -378,28 +513,359 @@ pub(crate) fn check_invocation_status(
     invoke_id: String,
 ) -> Result<(), String> {
     let community = crate::try_find_community(vemodel::get_belongs_to(content_id))?;
-    let id = openai::retrieve_run(community.llm_vendor.key(), &session_id, &invoke_id)?;
+    let id = openai::retrieve_run(
+        community.llm_vendor.host(),
+        community.llm_vendor.key(),
+        &session_id,
+        &invoke_id,
+    )?;
     trace(id, HttpCallType::CheckInvocationStatus(content_id)).map_err(|e| e.to_string())
 }
 
+/// 2s, 4s, 8s ... up to 30s
+fn poll_backoff(polls: u32) -> std::time::Duration {
+    std::time::Duration::from_secs((2u64 << polls.min(4)).min(30))
+}
+
+/// 10s, 20s, 40s ... up to 2 minutes
+fn retry_backoff(retries: u32) -> std::time::Duration {
+    std::time::Duration::from_secs((5u64 << retries.min(5)).min(120))
+}
+
+fn start_invocation(content_id: ContentId) -> Result<(), String> {
+    let invocation = Invocation {
+        started_at: timer::now(),
+        ..Default::default()
+    };
+    crate::save(&trie::to_invocation_key(content_id), &invocation)
+}
+
+/// the invocations started before the tracking are treated as just started
+fn get_invocation(content_id: ContentId) -> Result<Invocation, String> {
+    Ok(
+        crate::find::<Invocation>(&trie::to_invocation_key(content_id))?.unwrap_or_else(|| {
+            Invocation {
+                started_at: timer::now(),
+                ..Default::default()
+            }
+        }),
+    )
+}
+
+fn save_invocation(content_id: ContentId, invocation: &Invocation) -> Result<(), String> {
+    crate::save(&trie::to_invocation_key(content_id), invocation)
+}
+
+fn is_overdue(invocation: &Invocation) -> bool {
+    invocation.started_at + MAX_INVOCATION_SECS < timer::now()
+}
+
+fn finish_invocation(content_id: ContentId) -> Result<(), String> {
+    storage::del(&trie::to_invocation_key(content_id)).map_err(|e| e.to_string())
+}
+
+fn is_rate_limited(response: &CallResult<HttpResponse>) -> bool {
+    matches!(response, Ok(r) if r.head.status == 429)
+}
+
+/// give up the invocation and tell the users, only once for each invocation
+fn fail_invocation(
+    community: &Community,
+    content_id: ContentId,
+    reason: &str,
+) -> Result<(), String> {
+    vrs_core_sdk::println!("invocation of {} failed: {}", content_id, reason);
+    end_invocation(community, content_id, CANNOT_ANSWER)
+}
+
+/// stop the invocation once the budget is used up halfway, e.g. between the tool rounds
+fn pause_invocation(community: &Community, content_id: ContentId) -> Result<(), String> {
+    vrs_core_sdk::println!("invocation of {} is paused by the budget", content_id);
+    end_invocation(community, content_id, PAUSED_BY_BUDGET)
+}
+
+fn end_invocation(community: &Community, content_id: ContentId, text: &str) -> Result<(), String> {
+    let key = trie::to_invocation_key(content_id);
+    if crate::find::<Invocation>(&key)?.is_none() {
+        return Ok(());
+    }
+    storage::del(&key).map_err(|e| e.to_string())?;
+    reply(community, content_id, text)
+}
+
+/// retry the rate limited invocation with backoff until the deadline
+fn retry_or_fail(community: &Community, content_id: ContentId, reason: &str) -> Result<(), String> {
+    let mut invocation = get_invocation(content_id)?;
+    if invocation.retries >= MAX_INVOCATION_RETRIES || is_overdue(&invocation) {
+        return fail_invocation(community, content_id, reason);
+    }
+    invocation.retries += 1;
+    save_invocation(content_id, &invocation)?;
+    set_timer!(
+        retry_backoff(invocation.retries),
+        retry_invocation,
+        content_id
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+#[timer]
+pub(crate) fn retry_invocation(content_id: ContentId) -> Result<(), String> {
+    if crate::find::<Invocation>(&trie::to_invocation_key(content_id))?.is_none() {
+        return Ok(());
+    }
+    let community = crate::try_find_community(vemodel::get_belongs_to(content_id))?;
+    if crate::usage::is_over_budget(&community)? {
+        return pause_invocation(&community, content_id);
+    }
+    provider_of(&community).retry(&community, content_id)
+}
+
+/// scheduled before the queries were capped
 #[timer]
 pub(crate) fn check_issue_token_tx(
     community_id: CommunityId,
     tx_hash: String,
+) -> Result<(), String> {
+    query_issue_tx(community_id, tx_hash, 0)
+}
+
+#[timer]
+pub(crate) fn check_issue_tx(
+    community_id: CommunityId,
+    tx_hash: String,
+    attempts: u32,
+) -> Result<(), String> {
+    query_issue_tx(community_id, tx_hash, attempts)
+}
+
+fn query_issue_tx(community_id: CommunityId, tx_hash: String, attempts: u32) -> Result<(), String> {
+    let id = bsc::initiate_query_bsc_transaction(&tx_hash)?;
+    trace(
+        id,
+        HttpCallType::CheckingIssueTx(community_id, tx_hash, attempts),
+    )
+    .map_err(|e| e.to_string())
+}
+
+#[timer]
+pub(crate) fn check_refund_tx(
+    community_id: CommunityId,
+    tx_hash: String,
+    attempts: u32,
 ) -> Result<(), String> {
     let id = bsc::initiate_query_bsc_transaction(&tx_hash)?;
-    trace(id, HttpCallType::QueryIssueResult(community_id, tx_hash)).map_err(|e| e.to_string())
+    trace(
+        id,
+        HttpCallType::CheckingRefundTx(community_id, tx_hash, attempts),
+    )
+    .map_err(|e| e.to_string())
+}
+
+const MAX_TX_QUERIES: u32 = 12;
+
+/// 5s, 10s, 20s ... up to 5 minutes
+fn tx_query_backoff(attempts: u32) -> std::time::Duration {
+    std::time::Duration::from_secs((5u64 << attempts.min(6)).min(300))
+}
+
+fn on_issue_result(
+    community_id: CommunityId,
+    tx: String,
+    attempts: u32,
+    response: CallResult<HttpResponse>,
+) -> Result<(), String> {
+    match bsc::on_checking_issue_result(response) {
+        Ok(bsc::IssueResult::Deployed {
+            fund_contract,
+            token_contract,
+        }) => {
+            let mut community = crate::try_find_community(community_id)?;
+            let contract_addr = AccountId::from_str(fund_contract.as_str())?;
+            community.agent_contract = Some(contract_addr);
+            if community.token_info.new_issue {
+                community.token_info.contract = token_contract
+                    .map(|c| AccountId::from_str(c.as_str()).unwrap())
+                    .ok_or("The tx should include a token contract".to_string())?;
+                storage::put(
+                    &crate::trie::to_balance_key(community_id, community.agent_pubkey),
+                    community.token_info.total_issuance.encode(),
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            crate::agent::init_agent(&community)?;
+            community.status = CommunityStatus::Active;
+            let community_key = to_community_key(community.id());
+            crate::save(&community_key, &community)?;
+            crate::save_event(Event::CommunityUpdated(community.id()))
+        }
+        // issue again on retry
+        Ok(bsc::IssueResult::Reverted) => {
+            let mut payment = find_activation_payment(community_id)?;
+            payment.issue_tx = None;
+            crate::save(&trie::to_activation_key(community_id), &payment)?;
+            fail_activation(community_id, "The issuing tx reverted".to_string())
+        }
+        _ if attempts + 1 < MAX_TX_QUERIES => {
+            let _ = set_timer!(
+                tx_query_backoff(attempts + 1),
+                check_issue_tx,
+                community_id,
+                tx,
+                attempts + 1,
+            );
+            Ok(())
+        }
+        _ => fail_activation(
+            community_id,
+            format!("The issuing tx {} is not confirmed", tx),
+        ),
+    }
+}
+
+fn find_activation_payment(community_id: CommunityId) -> Result<ActivationPayment, String> {
+    crate::find::<ActivationPayment>(&trie::to_activation_key(community_id))?
+        .ok_or("Activation payment not found".to_string())
+}
+
+fn fail_activation(community_id: CommunityId, reason: String) -> Result<(), String> {
+    let mut community = try_find_community(community_id)?;
+    community.status = CommunityStatus::CreateFailed(reason);
+    crate::save(&trie::to_community_key(community_id), &community)?;
+    crate::save_event(Event::CommunityUpdated(community_id))
+}
+
+/// the replaced tx might be mined already, so it is queried again
+fn on_refund_rejected(community_id: CommunityId, reason: String) -> Result<(), String> {
+    match find_activation_payment(community_id)?.refund_tx {
+        Some(tx) => set_timer!(tx_query_backoff(0), check_refund_tx, community_id, tx, 0u32)
+            .map(|_| ())
+            .map_err(|e| e.to_string()),
+        None => abort_refund(community_id, reason),
+    }
+}
+
+/// the same payment tx shouldn't be accepted twice, the txs up to `last_transfer_block` of
+/// the payer were accepted before the txs are recorded
+fn consume_tx(community_id: CommunityId, tx: &bsc::TransactionDetails) -> Result<bool, String> {
+    let payer = AccountId::from_str(&tx.sender)?;
+    if get_account_info(payer)?.last_transfer_block >= tx.block_number {
+        return Ok(false);
+    }
+    let hash: [u8; 32] = hex::decode(tx.hash.trim_start_matches("0x"))
+        .ok()
+        .and_then(|v| v.try_into().ok())
+        .ok_or("Invalid tx hash".to_string())?;
+    let key = trie::to_consumed_tx_key(community_id, hash);
+    if crate::find::<u32>(&key)?.is_some() {
+        return Ok(false);
+    }
+    crate::save(&key, &1u32)?;
+    Ok(true)
+}
+
+fn abort_refund(community_id: CommunityId, reason: String) -> Result<(), String> {
+    let mut payment = find_activation_payment(community_id)?;
+    payment.refunding = false;
+    crate::save(&trie::to_activation_key(community_id), &payment)?;
+    fail_activation(community_id, format!("Refunding failed: {}", reason))
+}
+
+/// continue the failed activation without paying again
+pub(crate) fn retry_activation(community: &Community) -> Result<(), String> {
+    let community_id = community.id();
+    matches!(community.status, CommunityStatus::CreateFailed(_))
+        .then(|| ())
+        .ok_or("Only the failed activation could be retried".to_string())?;
+    let payment = find_activation_payment(community_id)?;
+    (!payment.refunding && payment.refund_tx.is_none())
+        .then(|| ())
+        .ok_or("The activation fee is refunded".to_string())?;
+    let mut community = try_find_community(community_id)?;
+    match payment.issue_tx {
+        // the tx was sent, query it again in case of it is confirmed lately
+        Some(tx) => {
+            community.status = CommunityStatus::TokenIssued(tx.clone());
+            set_timer!(tx_query_backoff(0), check_issue_tx, community_id, tx, 0u32)
+                .map_err(|e| e.to_string())?;
+        }
+        None => {
+            bsc::issue_token(&community)?;
+            community.status = CommunityStatus::PendingCreation;
+        }
+    }
+    crate::save(&trie::to_community_key(community_id), &community)?;
+    crate::save_event(Event::CommunityUpdated(community_id))
+}
+
+/// send the activation fee back to the payer
+pub(crate) fn refund_activation(community: &Community) -> Result<(), String> {
+    let community_id = community.id();
+    matches!(community.status, CommunityStatus::CreateFailed(_))
+        .then(|| ())
+        .ok_or("Only the failed activation could be refunded".to_string())?;
+    let mut payment = find_activation_payment(community_id)?;
+    (!payment.refunding)
+        .then(|| ())
+        .ok_or("The activation fee is being refunded".to_string())?;
+    match (payment.refund_tx.clone(), payment.refund_nonce) {
+        // the tx was never confirmed, replace it with the same nonce so that only one is mined
+        (Some(_), Some(nonce)) => {
+            let id = bsc::query_balance(&community.agent_pubkey.to_string())?;
+            trace(id, HttpCallType::QueryRefundBalance(community_id, nonce))
+                .map_err(|e| e.to_string())?;
+        }
+        (Some(tx), None) => {
+            set_timer!(tx_query_backoff(0), check_refund_tx, community_id, tx, 0u32)
+                .map_err(|e| e.to_string())?;
+        }
+        (None, _) => {
+            let id = bsc::query_nonce(&community.agent_pubkey.to_string())?;
+            trace(id, HttpCallType::QueryRefundNonce(community_id)).map_err(|e| e.to_string())?;
+        }
+    }
+    payment.refunding = true;
+    crate::save(&trie::to_activation_key(community_id), &payment)
 }
 
 pub(crate) fn init_agent(community: &Community) -> Result<(), String> {
-    let prompt = decorate_prompt(
-        &community.name,
-        &community.agent_pubkey,
-        &community.prompt,
-        &community.token_info,
-    );
-    let id = openai::create_assistant(community.llm_vendor.key(), &community.name, &prompt)?;
-    trace(id, HttpCallType::CreatingAgent(community.id())).map_err(|e| e.to_string())
+    provider_of(community).init_agent(community)
+}
+
+pub(crate) fn update_agent(community: &Community) -> Result<(), String> {
+    provider_of(community).update_agent(community, 0)
+}
+
+const MAX_AGENT_SYNCS: u32 = 5;
+
+/// the settings are saved before the vendor applies them, so push the latest ones
+/// again with backoff until the vendor accepts
+fn resync_agent(community_id: CommunityId, attempts: u32) -> Result<(), String> {
+    if attempts >= MAX_AGENT_SYNCS {
+        vrs_core_sdk::println!("gave up syncing the agent of {}", community_id);
+        return Ok(());
+    }
+    set_timer!(
+        retry_backoff(attempts + 1),
+        sync_agent,
+        community_id,
+        attempts + 1
+    )
+    .map(|_| ())
+    .map_err(|e| e.to_string())
+}
+
+#[timer]
+pub(crate) fn sync_agent(community_id: CommunityId, attempts: u32) -> Result<(), String> {
+    let community = crate::try_find_community(community_id)?;
+    match provider_of(&community).update_agent(&community, attempts) {
+        Ok(()) => Ok(()),
+        Err(e) => {
+            vrs_core_sdk::println!("syncing the agent of {} failed: {}", community_id, e);
+            resync_agent(community_id, attempts)
+        }
+    }
 }
 
 pub(crate) fn create_session_and_run(
@@ -407,33 +873,12 @@ pub(crate) fn create_session_and_run(
     thread: &Thread,
     text: &str,
 ) -> Result<(), String> {
-    let id = openai::create_thread_and_run(
-        community.llm_vendor.key(),
-        &community.llm_assistant_id,
-        thread,
-        text,
-    )?;
-    trace(id, HttpCallType::InvokingLLM(thread.id())).map_err(|e| e.to_string())
-}
-
-fn run(content_id: ContentId) -> Result<(), String> {
-    if vemodel::is_thread(content_id) {
-        return Ok(());
+    crate::ensure_active(community)?;
+    if crate::usage::is_over_budget(community)? {
+        return reply(community, thread.id(), PAUSED_BY_BUDGET);
     }
-    let comment = crate::find::<Comment>(&crate::trie::to_content_key(content_id))?
-        .ok_or("Comment not found".to_string())?;
-    let community_id = comment.community_id();
-    let community = crate::find::<Community>(&crate::trie::to_community_key(community_id))?
-        .ok_or("Community not found".to_string())?;
-    let thread_id = comment.thread_id();
-    let thread = crate::find::<Thread>(&crate::trie::to_content_key(thread_id))?
-        .ok_or("Thread not found".to_string())?;
-    let id = openai::create_run(
-        community.llm_vendor.key(),
-        &community.llm_assistant_id,
-        &thread.llm_session_id,
-    )?;
-    trace(id, HttpCallType::InvokingLLM(content_id)).map_err(|e| e.to_string())
+    start_invocation(thread.id())?;
+    provider_of(community).create_session_and_run(community, thread, text)
 }
 
 pub(crate) fn append_message_then_run(
@@ -442,64 +887,58 @@ pub(crate) fn append_message_then_run(
     comment: &Comment,
     text: &str,
 ) -> Result<(), String> {
-    let id = openai::append_message(
-        community.llm_vendor.key(),
-        &thread.llm_session_id,
-        comment,
-        text,
-    )?;
-    trace(id, HttpCallType::AppendingMessage(comment.id())).map_err(|e| e.to_string())
-}
-
-fn pull_messages(
-    key: &str,
-    content_id: ContentId,
-    session_id: &str,
-    invoke_id: &str,
-) -> Result<(), String> {
-    let id = openai::list_messages(key, session_id, invoke_id)?;
-    trace(id, HttpCallType::PullingMessage(content_id)).map_err(|e| e.to_string())
+    crate::ensure_active(community)?;
+    if crate::usage::is_over_budget(community)? {
+        return reply(community, comment.id(), PAUSED_BY_BUDGET);
+    }
+    start_invocation(comment.id())?;
+    provider_of(community).append_message_then_run(community, thread, comment, text)
 }
 
-fn submit_tool_call(
-    key: &str,
-    content_id: ContentId,
-    session_id: &str,
-    invoke_id: &str,
-    call_result: Vec<(String, String)>,
-) -> Result<(), String> {
-    let id = openai::submit_tool_outputs(key, session_id, invoke_id, call_result)?;
-    trace(id, HttpCallType::SubmittingToolCall(content_id)).map_err(|e| e.to_string())
-}
-
-fn call_tool(on: &Community, func: &str, params: &str) -> Result<String, String> {
-    let json: serde_json::Value =
-        serde_json::from_str(params).map_err(|_| "Invalid parameters".to_string())?;
-    match func {
-        "agent_balance" => crate::balance_of(on.id(), on.agent_pubkey),
-        "transfer" => {
-            let recipient = json["recipient"].as_str().ok_or("Invalid recipient")?;
-            let recipient =
-                AccountId::from_str(recipient).map_err(|_| "Invalid param: recipient")?;
-            let amount = json["amount"].as_u64().ok_or("Invalid amount")? as u128;
-            crate::transfer(on.id(), on.agent_pubkey, recipient, amount).map(|_| "Ok".to_string())
-        }
-        "balance_of" => {
-            let account = json["account"].as_str().ok_or("Invalid account")?;
-            let account = AccountId::from_str(account).map_err(|_| "Invalid param: account")?;
-            crate::balance_of(on.id(), account)
+/// the authors of the thread and the comment which the agent is answering
+fn run_authors(content_id: ContentId) -> Result<Vec<AccountId>, String> {
+    let thread_id = content_id & (u128::MAX - u32::MAX as u128);
+    let mut authors = vec![];
+    if let Some(thread) = crate::find::<Thread>(&trie::to_content_key(thread_id))? {
+        authors.push(thread.author);
+    }
+    if trie::is_comment(content_id) {
+        if let Some(comment) = crate::find::<Comment>(&trie::to_content_key(content_id))? {
+            authors.push(comment.author);
         }
-        _ => Err("Invalid tool".to_string()),
     }
+    Ok(authors)
+}
+
+/// post the reply of the agent as a comment
+fn reply(community: &Community, content_id: ContentId, text: &str) -> Result<(), String> {
+    let id = crate::allocate_comment_id(content_id)?;
+    let key = crate::trie::to_content_key(id);
+    let reply_to = crate::trie::is_comment(content_id).then(|| hex::encode(content_id.encode()));
+    let comment = Comment {
+        id: hex::encode(id.encode()),
+        content: crate::compress(text)?,
+        legacy_images: vec![],
+        author: community.agent_pubkey,
+        mention: vec![],
+        reply_to,
+        created_time: timer::now() as i64,
+        images: vec![],
+        hidden: false,
+    };
+    crate::save(&key, &comment)?;
+    crate::save_event(Event::CommentPosted(id))
 }
 
 pub(crate) fn check_transfering(community: &Community, tx: String) -> Result<(), String> {
     match community.status.clone() {
         CommunityStatus::PendingCreation | CommunityStatus::Active => Ok(()),
         CommunityStatus::TokenIssued(_) => Ok(()),
-        CommunityStatus::WaitingTx(_)
-        | CommunityStatus::Frozen(_)
-        | CommunityStatus::CreateFailed(_) => {
+        CommunityStatus::Frozen(..) => Err("The community is frozen".to_string()),
+        CommunityStatus::CreateFailed(_) => {
+            Err("The activation failed, please retry or refund it".to_string())
+        }
+        CommunityStatus::WaitingTx(_) => {
             let id = bsc::initiate_query_bsc_transaction(&tx)?;
             trace(id, HttpCallType::CheckingActivateTx(community.id())).map_err(|e| e.to_string())
         }
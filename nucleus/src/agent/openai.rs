@@ -1,70 +1,422 @@
+use super::{HttpCallType, InvocationStatus, LlmProvider};
 use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
-use vemodel::{Comment, Thread};
+use vemodel::{Comment, Community, ContentId, Thread};
 use vrs_core_sdk::http::{self, HttpMethod, HttpRequest, HttpResponse, RequestHead};
 use vrs_core_sdk::CallResult;
 
-pub(crate) fn create_assistant(key: &str, name: &str, prompt: &str) -> Result<u64, String> {
+/// the Assistants v2 API, the sessions are kept by OpenAI
+pub(crate) struct Assistants;
+
+impl LlmProvider for Assistants {
+    fn init_agent(&self, community: &Community) -> Result<(), String> {
+        let prompt = super::decorate_prompt(
+            &community.name,
+            &community.agent_pubkey,
+            &community.prompt,
+            &community.token_info,
+        );
+        let id = create_assistant(
+            community.llm_vendor.host(),
+            community.llm_vendor.key(),
+            &community.name,
+            &prompt,
+            &super::model_of(&community.llm_vendor, &community.generation),
+            super::tools::definitions(community),
+        )?;
+        super::trace(id, HttpCallType::CreatingAgent(community.id())).map_err(|e| e.to_string())
+    }
+
+    fn update_agent(&self, community: &Community, attempts: u32) -> Result<(), String> {
+        // the assistant would be created with the latest settings after activated
+        if community.llm_assistant_id.is_empty() {
+            return Ok(());
+        }
+        let prompt = super::decorate_prompt(
+            &community.name,
+            &community.agent_pubkey,
+            &community.prompt,
+            &community.token_info,
+        );
+        let id = modify_assistant(
+            community,
+            &prompt,
+            &super::model_of(&community.llm_vendor, &community.generation),
+            super::tools::definitions(community),
+        )?;
+        super::trace(id, HttpCallType::SyncingAgent(community.id(), attempts))
+            .map_err(|e| e.to_string())
+    }
+
+    fn create_session_and_run(
+        &self,
+        community: &Community,
+        thread: &Thread,
+        text: &str,
+    ) -> Result<(), String> {
+        let id = create_thread_and_run(community, thread, text)?;
+        super::trace(id, HttpCallType::InvokingLLM(thread.id())).map_err(|e| e.to_string())
+    }
+
+    fn retry(&self, community: &Community, content_id: ContentId) -> Result<(), String> {
+        let invocation = super::get_invocation(content_id)?;
+        if !invocation.run_id.is_empty() {
+            let id = retrieve_run(
+                community.llm_vendor.host(),
+                community.llm_vendor.key(),
+                &invocation.session_id,
+                &invocation.run_id,
+            )?;
+            return super::trace(id, HttpCallType::CheckInvocationStatus(content_id))
+                .map_err(|e| e.to_string());
+        }
+        if vemodel::is_thread(content_id) {
+            let thread = crate::find::<Thread>(&crate::trie::to_content_key(content_id))?
+                .ok_or("Thread not found".to_string())?;
+            if thread.llm_session_id.is_empty() {
+                let limit = community.content_rules().max_content_len as usize;
+                let text = crate::decompress(&thread.content, limit)?;
+                return self.create_session_and_run(community, &thread, &text);
+            }
+            let id = create_run(community, &thread.llm_session_id)?;
+            return super::trace(id, HttpCallType::InvokingLLM(content_id))
+                .map_err(|e| e.to_string());
+        }
+        // the session is recorded only after the comment is appended
+        if invocation.session_id.is_empty() {
+            let comment = crate::find::<Comment>(&crate::trie::to_content_key(content_id))?
+                .ok_or("Comment not found".to_string())?;
+            let thread = crate::find::<Thread>(&crate::trie::to_content_key(comment.thread_id()))?
+                .ok_or("Thread not found".to_string())?;
+            let limit = community.content_rules().max_content_len as usize;
+            let text = crate::decompress(&comment.content, limit)?;
+            return self.append_message_then_run(community, &thread, &comment, &text);
+        }
+        run(community, content_id)
+    }
+
+    fn append_message_then_run(
+        &self,
+        community: &Community,
+        thread: &Thread,
+        comment: &Comment,
+        text: &str,
+    ) -> Result<(), String> {
+        let id = append_message(
+            community.llm_vendor.host(),
+            community.llm_vendor.key(),
+            &thread.llm_session_id,
+            comment,
+            text,
+        )?;
+        super::trace(id, HttpCallType::AppendingMessage(comment.id())).map_err(|e| e.to_string())
+    }
+
+    fn on_response(
+        &self,
+        community: &Community,
+        call_type: HttpCallType,
+        response: CallResult<HttpResponse>,
+    ) -> Result<(), String> {
+        match call_type {
+            HttpCallType::CreatingAgent(community_id) => {
+                let assistant_id = resolve_assistant_id(response)?;
+                let mut community = crate::try_find_community(community_id)?;
+                community.llm_assistant_id = assistant_id;
+                crate::save(&crate::trie::to_community_key(community_id), &community)?;
+            }
+            // traced before the failed updates were retried
+            HttpCallType::UpdatingAgent(_) => {
+                let assistant = resolve_assistant_id(response)?;
+                (assistant == community.llm_assistant_id)
+                    .then(|| ())
+                    .ok_or("The updated assistant mismatched".to_string())?;
+            }
+            HttpCallType::SyncingAgent(community_id, attempts) => {
+                let synced = resolve_assistant_id(response).and_then(|assistant| {
+                    (assistant == community.llm_assistant_id)
+                        .then(|| ())
+                        .ok_or("The updated assistant mismatched".to_string())
+                });
+                if let Err(e) = synced {
+                    vrs_core_sdk::println!("syncing the agent of {} failed: {}", community_id, e);
+                    super::resync_agent(community_id, attempts)?;
+                }
+            }
+            HttpCallType::AppendingMessage(content_id) => {
+                if super::is_rate_limited(&response) {
+                    return super::retry_or_fail(community, content_id, "rate limited");
+                }
+                let message = match super::parse_response::<MessageObject>(response) {
+                    Ok(message) => message,
+                    Err(e) => return super::fail_invocation(community, content_id, &e),
+                };
+                let mut invocation = super::get_invocation(content_id)?;
+                invocation.session_id = message.thread_id;
+                super::save_invocation(content_id, &invocation)?;
+                run(community, content_id)?;
+            }
+            HttpCallType::InvokingLLM(content_id)
+            | HttpCallType::CheckInvocationStatus(content_id)
+            | HttpCallType::SubmittingToolCall(content_id) => {
+                if super::is_rate_limited(&response) {
+                    return super::retry_or_fail(community, content_id, "rate limited");
+                }
+                // TODO define the invocation object to replace the RunObject
+                let run = match super::parse_response::<RunObject>(response) {
+                    Ok(run) => run,
+                    Err(e) => return super::fail_invocation(community, content_id, &e),
+                };
+                let status = match run.status.as_str() {
+                    "queued" | "in_progress" | "cancelling" => InvocationStatus::Running,
+                    "completed" => InvocationStatus::Completed,
+                    "requires_action" => InvocationStatus::WaitingFunctionCall,
+                    "incomplete" => InvocationStatus::Incomplete,
+                    "expired" => InvocationStatus::Expired,
+                    "cancelled" => InvocationStatus::Cancelled,
+                    _ => InvocationStatus::Failed,
+                };
+                if vemodel::is_thread(content_id) {
+                    let key = crate::trie::to_content_key(content_id);
+                    let mut thread =
+                        crate::find::<Thread>(&key)?.ok_or("Thread not found".to_string())?;
+                    if thread.llm_session_id.is_empty() {
+                        thread.llm_session_id = run.thread_id.clone();
+                        crate::save(&key, &thread)?;
+                    }
+                }
+                if let Some(usage) = &run.usage {
+                    crate::usage::record(
+                        community,
+                        &run.model,
+                        usage.prompt_tokens,
+                        usage.completion_tokens,
+                    )?;
+                }
+                let mut invocation = super::get_invocation(content_id)?;
+                invocation.session_id = run.thread_id.clone();
+                invocation.run_id = run.id.clone();
+                match status {
+                    InvocationStatus::Running | InvocationStatus::WaitingFunctionCall
+                        if super::is_overdue(&invocation) =>
+                    {
+                        let id = cancel_run(
+                            community.llm_vendor.host(),
+                            community.llm_vendor.key(),
+                            &run.thread_id,
+                            &run.id,
+                        )?;
+                        super::trace(id, HttpCallType::CancellingRun(content_id))
+                            .map_err(|e| e.to_string())?;
+                        super::fail_invocation(community, content_id, "timeout")?;
+                    }
+                    // the tool outputs would start another round of the LLM
+                    InvocationStatus::WaitingFunctionCall
+                        if crate::usage::is_over_budget(community)? =>
+                    {
+                        let id = cancel_run(
+                            community.llm_vendor.host(),
+                            community.llm_vendor.key(),
+                            &run.thread_id,
+                            &run.id,
+                        )?;
+                        super::trace(id, HttpCallType::CancellingRun(content_id))
+                            .map_err(|e| e.to_string())?;
+                        super::pause_invocation(community, content_id)?;
+                    }
+                    InvocationStatus::Running => {
+                        invocation.polls += 1;
+                        super::save_invocation(content_id, &invocation)?;
+                        super::schedule_invocation_check(
+                            content_id,
+                            run.thread_id.clone(),
+                            run.id.clone(),
+                            invocation.polls,
+                        )?;
+                    }
+                    InvocationStatus::WaitingFunctionCall => {
+                        super::save_invocation(content_id, &invocation)?;
+                        let ctx = super::tools::ToolContext {
+                            community,
+                            run_id: &run.id,
+                            authors: super::run_authors(content_id)?,
+                        };
+                        if let Some(actions) = run.required_action {
+                            let call_result = actions
+                                .submit_tool_outputs
+                                .tool_calls
+                                .into_iter()
+                                .map(|call| {
+                                    (
+                                        call.id.clone(),
+                                        match super::tools::call(
+                                            &ctx,
+                                            &call.function.name,
+                                            &call.function.arguments,
+                                        ) {
+                                            Ok(result) => result,
+                                            Err(e) => e,
+                                        },
+                                    )
+                                })
+                                .collect::<Vec<(String, String)>>();
+                            let id = submit_tool_outputs(
+                                community.llm_vendor.host(),
+                                community.llm_vendor.key(),
+                                &run.thread_id,
+                                &run.id,
+                                call_result,
+                            )?;
+                            super::trace(id, HttpCallType::SubmittingToolCall(content_id))
+                                .map_err(|e| e.to_string())?;
+                        }
+                    }
+                    // the partial answer of an incomplete run is still worth replying
+                    InvocationStatus::Completed | InvocationStatus::Incomplete => {
+                        if status == InvocationStatus::Incomplete {
+                            vrs_core_sdk::println!("run {} is incomplete", run.id);
+                        }
+                        super::save_invocation(content_id, &invocation)?;
+                        let id = list_messages(
+                            community.llm_vendor.host(),
+                            community.llm_vendor.key(),
+                            &run.thread_id,
+                            &run.id,
+                        )?;
+                        super::trace(id, HttpCallType::PullingMessage(content_id))
+                            .map_err(|e| e.to_string())?;
+                    }
+                    InvocationStatus::Failed
+                        if run
+                            .last_error
+                            .as_ref()
+                            .map(|e| e.code == "rate_limit_exceeded")
+                            .unwrap_or_default() =>
+                    {
+                        // start a new run on the same session
+                        invocation.run_id = Default::default();
+                        super::save_invocation(content_id, &invocation)?;
+                        super::retry_or_fail(community, content_id, "rate limited")?;
+                    }
+                    InvocationStatus::Failed
+                    | InvocationStatus::Expired
+                    | InvocationStatus::Cancelled => {
+                        let reason =
+                            format!("run {} is {}: {:?}", run.id, run.status, run.last_error);
+                        super::fail_invocation(community, content_id, &reason)?;
+                    }
+                }
+            }
+            HttpCallType::PullingMessage(content_id) => {
+                if super::is_rate_limited(&response) {
+                    return super::retry_or_fail(community, content_id, "rate limited");
+                }
+                // TODO define the message object to replace the MessageObject
+                let messages = match resolve_messages(response) {
+                    Ok(messages) => messages,
+                    Err(e) => return super::fail_invocation(community, content_id, &e),
+                };
+                let reply = messages
+                    .data
+                    .into_iter()
+                    .find(|m| m.role == MessageRole::assistant);
+                match reply {
+                    Some(reply) => {
+                        let content = reply
+                            .content
+                            .into_iter()
+                            .filter(|c| c.content_type == "text")
+                            .map(|c| c.text.value)
+                            .collect::<Vec<String>>()
+                            .join("\n");
+                        super::finish_invocation(content_id)?;
+                        super::reply(community, content_id, &content)?;
+                    }
+                    None => super::fail_invocation(community, content_id, "no reply")?,
+                }
+            }
+            HttpCallType::CancellingRun(content_id) => {
+                if let Err(e) = super::parse_response::<RunObject>(response) {
+                    vrs_core_sdk::println!("cancelling the run of {} failed: {}", content_id, e);
+                }
+            }
+            _ => return Err("Not an Assistants call".to_string()),
+        }
+        Ok(())
+    }
+}
+
+/// run the session of the thread again after a comment is appended
+fn run(community: &Community, content_id: ContentId) -> Result<(), String> {
+    if vemodel::is_thread(content_id) {
+        return Ok(());
+    }
+    let comment = crate::find::<Comment>(&crate::trie::to_content_key(content_id))?
+        .ok_or("Comment not found".to_string())?;
+    crate::ensure_active(community)?;
+    let thread_id = comment.thread_id();
+    let thread = crate::find::<Thread>(&crate::trie::to_content_key(thread_id))?
+        .ok_or("Thread not found".to_string())?;
+    let id = create_run(community, &thread.llm_session_id)?;
+    super::trace(id, HttpCallType::InvokingLLM(content_id)).map_err(|e| e.to_string())
+}
+
+pub(crate) fn create_assistant(
+    host: &str,
+    key: &str,
+    name: &str,
+    prompt: &str,
+    model: &str,
+    tools: serde_json::Value,
+) -> Result<u64, String> {
     let mut headers = BTreeMap::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
     headers.insert("OpenAI-Beta".to_string(), "assistants=v2".to_string());
     headers.insert("Authorization".to_string(), format!("Bearer {}", key));
     let body = serde_json::json!({
         "instructions": prompt,
-        "model": "gpt-4o",
+        "model": model,
         "name": name,
-        "tools": [{
-            "type": "function",
-            "function": {
-                "name": "transfer",
-                "description": "Transfer funds to another user",
-                "parameters": {
-                    "type": "object",
-                    "properties": {
-                        "recipient": {
-                            "type": "string",
-                            "description": "The recipient user_id"
-                        },
-                        "amount": {
-                            "type": "number",
-                            "description": "The amount to transfer"
-                        }
-                    },
-                    "required": ["recipient", "amount"],
-                    "additionalProperties": false
-                },
-                "strict": true
-            }
-        },{
-            "type": "function",
-            "function": {
-                "name": "agent_balance",
-                "description": "Check the balance of the assistant"
-            }
-        },{
-            "type": "function",
-            "function": {
-                "name": "balance_of",
-                "description": "Query the balance of a user",
-                "parameters": {
-                    "type": "object",
-                    "properties": {
-                        "account_id": {
-                            "type": "string",
-                            "description": "The user_id to query"
-                        }
-                    },
-                    "required": ["account_id"],
-                    "additionalProperties": false
-                },
-                "strict": true
-            }
-        }]
+        "tools": tools,
+    });
+    let id = http::request(HttpRequest {
+        head: RequestHead {
+            method: HttpMethod::Post,
+            uri: format!("{}/assistants", host),
+            headers,
+        },
+        body: serde_json::to_vec(&body).expect("json;qed"),
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(id)
+}
+
+pub(crate) fn modify_assistant(
+    community: &Community,
+    prompt: &str,
+    model: &str,
+    tools: serde_json::Value,
+) -> Result<u64, String> {
+    let mut headers = BTreeMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers.insert("OpenAI-Beta".to_string(), "assistants=v2".to_string());
+    headers.insert(
+        "Authorization".to_string(),
+        format!("Bearer {}", community.llm_vendor.key()),
+    );
+    let body = serde_json::json!({
+        "instructions": prompt,
+        "model": model,
+        "tools": tools,
     });
     let id = http::request(HttpRequest {
         head: RequestHead {
             method: HttpMethod::Post,
-            uri: "https://api.openai.com/v1/assistants".to_string(),
+            uri: format!(
+                "{}/assistants/{}",
+                community.llm_vendor.host(),
+                community.llm_assistant_id
+            ),
             headers,
         },
         body: serde_json::to_vec(&body).expect("json;qed"),
@@ -78,16 +430,19 @@ pub(crate) fn resolve_assistant_id(response: CallResult<HttpResponse>) -> Result
     Ok(assistant.id)
 }
 
+/// the generation parameters of the community are applied to every run
 pub(crate) fn create_thread_and_run(
-    key: &str,
-    assistant_id: &str,
+    community: &Community,
     thread: &Thread,
     text: &str,
 ) -> Result<u64, String> {
     let mut headers = BTreeMap::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
     headers.insert("OpenAI-Beta".to_string(), "assistants=v2".to_string());
-    headers.insert("Authorization".to_string(), format!("Bearer {}", key));
+    headers.insert(
+        "Authorization".to_string(),
+        format!("Bearer {}", community.llm_vendor.key()),
+    );
     let text_msg = serde_json::json!({
         "id": thread.id,
         "title": thread.title,
@@ -97,7 +452,7 @@ pub(crate) fn create_thread_and_run(
         "created_time": thread.created_time,
     });
     let mut body = serde_json::json!({
-        "assistant_id": assistant_id,
+        "assistant_id": community.llm_assistant_id,
         "thread": {
             "messages": [{
                 "role": "user",
@@ -111,16 +466,21 @@ pub(crate) fn create_thread_and_run(
     let contents = body["thread"]["messages"][0]["content"]
         .as_array_mut()
         .unwrap();
-    for img in thread.images.iter() {
+    for img in thread
+        .images
+        .iter()
+        .filter_map(|i| i.public_url(super::IPFS_GATEWAY))
+    {
         contents.push(serde_json::json!({
             "type": "image_url",
             "image_url": { "url": img },
         }));
     }
+    super::apply_generation(&mut body, community, "max_completion_tokens");
     let response = http::request(HttpRequest {
         head: RequestHead {
             method: HttpMethod::Post,
-            uri: "https://api.openai.com/v1/threads/runs".to_string(),
+            uri: format!("{}/threads/runs", community.llm_vendor.host()),
             headers,
         },
         body: serde_json::to_vec(&body).expect("json;qed"),
@@ -129,19 +489,23 @@ pub(crate) fn create_thread_and_run(
     Ok(response)
 }
 
-pub(crate) fn create_run(key: &str, assistant_id: &str, thread_id: &str) -> Result<u64, String> {
+pub(crate) fn create_run(community: &Community, thread_id: &str) -> Result<u64, String> {
     let mut headers = BTreeMap::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
     headers.insert("OpenAI-Beta".to_string(), "assistants=v2".to_string());
-    headers.insert("Authorization".to_string(), format!("Bearer {}", key));
-    let body = serde_json::json!({
-        "assistant_id": assistant_id,
+    headers.insert(
+        "Authorization".to_string(),
+        format!("Bearer {}", community.llm_vendor.key()),
+    );
+    let mut body = serde_json::json!({
+        "assistant_id": community.llm_assistant_id,
         "additional_instructions": "this is a comment",
     });
+    super::apply_generation(&mut body, community, "max_completion_tokens");
     let response = http::request(HttpRequest {
         head: RequestHead {
             method: HttpMethod::Post,
-            uri: format!("https://api.openai.com/v1/threads/{}/runs", thread_id),
+            uri: format!("{}/threads/{}/runs", community.llm_vendor.host(), thread_id),
             headers,
         },
         body: serde_json::to_vec(&body).expect("json;qed"),
@@ -151,6 +515,7 @@ pub(crate) fn create_run(key: &str, assistant_id: &str, thread_id: &str) -> Resu
 }
 
 pub(crate) fn submit_tool_outputs(
+    host: &str,
     key: &str,
     session_id: &str,
     invoke_id: &str,
@@ -176,8 +541,8 @@ pub(crate) fn submit_tool_outputs(
         head: RequestHead {
             method: HttpMethod::Post,
             uri: format!(
-                "https://api.openai.com/v1/threads/{}/runs/{}/submit_tool_outputs",
-                session_id, invoke_id
+                "{}/threads/{}/runs/{}/submit_tool_outputs",
+                host, session_id, invoke_id
             ),
             headers,
         },
@@ -187,7 +552,12 @@ pub(crate) fn submit_tool_outputs(
     Ok(response)
 }
 
-pub(crate) fn retrieve_run(key: &str, session_id: &str, invoke_id: &str) -> Result<u64, String> {
+pub(crate) fn retrieve_run(
+    host: &str,
+    key: &str,
+    session_id: &str,
+    invoke_id: &str,
+) -> Result<u64, String> {
     let mut headers = BTreeMap::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
     headers.insert("OpenAI-Beta".to_string(), "assistants=v2".to_string());
@@ -195,10 +565,29 @@ pub(crate) fn retrieve_run(key: &str, session_id: &str, invoke_id: &str) -> Resu
     let response = http::request(HttpRequest {
         head: RequestHead {
             method: HttpMethod::Get,
-            uri: format!(
-                "https://api.openai.com/v1/threads/{}/runs/{}",
-                session_id, invoke_id
-            ),
+            uri: format!("{}/threads/{}/runs/{}", host, session_id, invoke_id),
+            headers,
+        },
+        body: vec![],
+    })
+    .map_err(|e| e.to_string())?;
+    Ok(response)
+}
+
+pub(crate) fn cancel_run(
+    host: &str,
+    key: &str,
+    session_id: &str,
+    invoke_id: &str,
+) -> Result<u64, String> {
+    let mut headers = BTreeMap::new();
+    headers.insert("Content-Type".to_string(), "application/json".to_string());
+    headers.insert("OpenAI-Beta".to_string(), "assistants=v2".to_string());
+    headers.insert("Authorization".to_string(), format!("Bearer {}", key));
+    let response = http::request(HttpRequest {
+        head: RequestHead {
+            method: HttpMethod::Post,
+            uri: format!("{}/threads/{}/runs/{}/cancel", host, session_id, invoke_id),
             headers,
         },
         body: vec![],
@@ -208,6 +597,7 @@ pub(crate) fn retrieve_run(key: &str, session_id: &str, invoke_id: &str) -> Resu
 }
 
 pub(crate) fn append_message(
+    host: &str,
     key: &str,
     session_id: &str,
     comment: &Comment,
@@ -232,7 +622,11 @@ pub(crate) fn append_message(
         }],
     });
     let contents = body["content"].as_array_mut().unwrap();
-    for img in comment.images.iter() {
+    for img in comment
+        .images
+        .iter()
+        .filter_map(|i| i.public_url(super::IPFS_GATEWAY))
+    {
         contents.push(serde_json::json!({
             "type": "image_url",
             "image_url": { "url": img },
@@ -241,7 +635,7 @@ pub(crate) fn append_message(
     let response = http::request(HttpRequest {
         head: RequestHead {
             method: HttpMethod::Post,
-            uri: format!("https://api.openai.com/v1/threads/{}/messages", session_id),
+            uri: format!("{}/threads/{}/messages", host, session_id),
             headers,
         },
         body: serde_json::to_vec(&body).expect("json;qed"),
@@ -250,7 +644,12 @@ pub(crate) fn append_message(
     Ok(response)
 }
 
-pub(crate) fn list_messages(key: &str, session_id: &str, invoke_id: &str) -> Result<u64, String> {
+pub(crate) fn list_messages(
+    host: &str,
+    key: &str,
+    session_id: &str,
+    invoke_id: &str,
+) -> Result<u64, String> {
     let mut headers = BTreeMap::new();
     headers.insert("Content-Type".to_string(), "application/json".to_string());
     headers.insert("OpenAI-Beta".to_string(), "assistants=v2".to_string());
@@ -259,8 +658,8 @@ pub(crate) fn list_messages(key: &str, session_id: &str, invoke_id: &str) -> Res
         head: RequestHead {
             method: HttpMethod::Get,
             uri: format!(
-                "https://api.openai.com/v1/threads/{}/messages?run_id={}",
-                session_id, invoke_id
+                "{}/threads/{}/messages?run_id={}",
+                host, session_id, invoke_id
             ),
             headers,
         },
@@ -437,6 +836,16 @@ pub struct RunObject {
     pub tools: Vec<Tools>,
     pub metadata: BTreeMap<String, String>,
     pub headers: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<Usage>,
+}
+
+/// only available when the run is in a terminal state
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub total_tokens: u64,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -553,7 +962,7 @@ pub struct ToolCalls {
     pub tool_calls: Vec<ToolCall>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct ToolCall {
     pub id: String,
     #[serde(rename = "type")]
@@ -561,7 +970,7 @@ pub struct ToolCall {
     pub function: FunctionCallWithParameter,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct FunctionCallWithParameter {
     pub name: String,
     pub arguments: String,
@@ -0,0 +1,181 @@
+use super::chat::{self, ChatCompletionObject};
+use super::{tools, HttpCallType};
+use crate::trie;
+use vemodel::*;
+use vrs_core_sdk::{http::HttpResponse, storage, timer, CallResult};
+
+const SUMMARY_COOLDOWN: u64 = 3600;
+/// a request without response is abandoned after this
+const PENDING_TIMEOUT: u64 = 600;
+const MAX_SUMMARY_LEN: usize = 4000;
+
+/// ask the LLM to summarize the thread, the price is paid to the agent in whole tokens
+pub(crate) fn request(
+    community: &Community,
+    thread: &Thread,
+    requester: AccountId,
+) -> Result<(), String> {
+    let thread_id = thread.id();
+    let now = timer::now();
+    if let Some(at) = thread.summarized_at {
+        (at as u64 + SUMMARY_COOLDOWN <= now)
+            .then(|| ())
+            .ok_or("The thread was summarized recently".to_string())?;
+    }
+    let key = trie::to_summary_request_key(thread_id);
+    let stale = crate::find::<SummaryRequest>(&key)?;
+    if let Some(ref pending) = stale {
+        (pending.requested_at + PENDING_TIMEOUT <= now)
+            .then(|| ())
+            .ok_or("The thread is being summarized".to_string())?;
+    }
+    (!crate::usage::is_over_budget(community)?)
+        .then(|| ())
+        .ok_or("The agent is paused by the budget".to_string())?;
+    let quota = crate::ratelimit::check_summary(community.id(), requester, now)?;
+    let mut request = SummaryRequest {
+        requester,
+        paid: community.summary_price.unwrap_or_default(),
+        requested_at: now,
+        cursor: None,
+        partial: None,
+    };
+    let instruction = next_chunk(community, thread, &mut request)?.expect("first chunk;qed");
+    // the late response of the abandoned request is ignored
+    if let Some(ref pending) = stale {
+        abandon(community, &key, pending)?;
+    }
+    if request.paid > 0 {
+        crate::transfer(
+            community.id(),
+            requester,
+            community.agent_pubkey,
+            request.paid,
+        )?;
+    }
+    crate::save(&key, &request)?;
+    if let Err(e) = chat::complete_once(
+        community,
+        instruction,
+        HttpCallType::SummarizingThread(thread_id, now),
+    ) {
+        abandon(community, &key, &request)?;
+        return Err(e);
+    }
+    crate::ratelimit::record_summary(community.id(), requester, quota)
+}
+
+/// the instruction with the comments after `cursor` and the summary of the earlier ones, the
+/// cursor is moved to the last comment included, `None` if all comments are summarized
+fn next_chunk(
+    community: &Community,
+    thread: &Thread,
+    request: &mut SummaryRequest,
+) -> Result<Option<String>, String> {
+    let (comments, last) = tools::comments_after(community, thread.id(), request.cursor)?;
+    if comments.is_empty() && request.partial.is_some() {
+        return Ok(None);
+    }
+    request.cursor = last.or(request.cursor);
+    let mut context = tools::thread_summary(thread);
+    context["content"] = tools::excerpt(community, &thread.content).into();
+    context["comments"] = comments.into();
+    let instruction = match request.partial {
+        None => format!(
+            "Summarize the thread and its comments below briefly and neutrally, \
+            in the language of the thread, reply with the summary only.\n\n{}",
+            context,
+        ),
+        Some(ref partial) => format!(
+            "Here is the summary of a thread and its earlier comments:\n\n{}\n\n\
+            Summarize the thread again with its later comments below briefly and neutrally, \
+            in the language of the thread, reply with the summary only.\n\n{}",
+            partial, context,
+        ),
+    };
+    Ok(Some(instruction))
+}
+
+/// the request is removed only after refunded, a failed refund is retried with the next request
+fn abandon(community: &Community, key: &[u8], request: &SummaryRequest) -> Result<(), String> {
+    refund(community, request)?;
+    storage::del(key).map_err(|e| e.to_string())
+}
+
+fn refund(community: &Community, request: &SummaryRequest) -> Result<(), String> {
+    if request.paid > 0 {
+        crate::transfer(
+            community.id(),
+            community.agent_pubkey,
+            request.requester,
+            request.paid,
+        )?;
+    }
+    Ok(())
+}
+
+fn resolve_summary(
+    community: &Community,
+    response: CallResult<HttpResponse>,
+) -> Result<String, String> {
+    let completion = super::parse_response::<ChatCompletionObject>(response)?;
+    if let Some(usage) = &completion.usage {
+        crate::usage::record(
+            community,
+            &completion.model,
+            usage.prompt_tokens,
+            usage.completion_tokens,
+        )?;
+    }
+    completion
+        .choices
+        .into_iter()
+        .next()
+        .and_then(|c| c.message.content)
+        .filter(|t| !t.trim().is_empty())
+        .map(|t| tools::bounded(t.trim().to_string(), MAX_SUMMARY_LEN))
+        .ok_or("Empty summary".to_string())
+}
+
+/// the request is identified by its `requested_at`, the next chunk is sent until all comments
+/// are summarized
+pub(crate) fn on_response(
+    thread_id: ContentId,
+    requested_at: u64,
+    response: CallResult<HttpResponse>,
+) -> Result<(), String> {
+    let key = trie::to_summary_request_key(thread_id);
+    let mut request = crate::find::<SummaryRequest>(&key)?
+        .filter(|r| r.requested_at == requested_at)
+        .ok_or("Summary request not found".to_string())?;
+    let community = crate::try_find_community(vemodel::get_belongs_to(thread_id))?;
+    let summary = match resolve_summary(&community, response) {
+        Ok(summary) => summary,
+        Err(e) => {
+            vrs_core_sdk::println!("summarizing {} failed: {}", thread_id, e);
+            return abandon(&community, &key, &request);
+        }
+    };
+    let content_key = trie::to_content_key(thread_id);
+    let mut thread = crate::find::<Thread>(&content_key)?.ok_or("Thread not found".to_string())?;
+    request.partial = Some(summary.clone());
+    request.requested_at = timer::now();
+    if let Some(instruction) = next_chunk(&community, &thread, &mut request)? {
+        if crate::usage::is_over_budget(&community)? {
+            vrs_core_sdk::println!("summarizing {} is paused by the budget", thread_id);
+            return abandon(&community, &key, &request);
+        }
+        crate::save(&key, &request)?;
+        let call_type = HttpCallType::SummarizingThread(thread_id, request.requested_at);
+        if let Err(e) = chat::complete_once(&community, instruction, call_type) {
+            vrs_core_sdk::println!("summarizing {} failed: {}", thread_id, e);
+            return abandon(&community, &key, &request);
+        }
+        return Ok(());
+    }
+    storage::del(&key).map_err(|e| e.to_string())?;
+    thread.summary = Some(summary);
+    thread.summarized_at = Some(timer::now() as i64);
+    crate::save(&content_key, &thread)?;
+    crate::save_event(Event::ThreadSummarized(thread_id))
+}
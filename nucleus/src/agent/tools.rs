@@ -0,0 +1,794 @@
+use crate::trie;
+use std::str::FromStr;
+use vemodel::{AccountId, Comment, Community, CommunityId, ContentId, Followee, Thread};
+use vrs_core_sdk::{codec::Decode, storage};
+
+/// the outputs of tools are bounded to fit in the context window
+pub(crate) const MAX_OUTPUT_LEN: usize = 8 * 1024;
+const MAX_EXCERPT_CHARS: usize = 1000;
+const MAX_LISTED_THREADS: u64 = 20;
+const MAX_LISTED_COMMENTS: usize = 20;
+const MAX_SCANNED_THREADS: usize = 100;
+
+/// what a tool is allowed to do on behalf of the community
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Permission {
+    /// reads the state of the community
+    Read,
+    /// spends the tokens of the agent
+    Transfer,
+    /// moderates the contents and users, recorded to be reverted
+    Moderate,
+}
+
+impl Permission {
+    /// the tools enabled for a new community, the moderation is opt-in
+    pub fn is_default(&self) -> bool {
+        matches!(self, Self::Read | Self::Transfer)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ParamType {
+    String,
+    Integer,
+    Boolean,
+}
+
+impl ParamType {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::String => "string",
+            Self::Integer => "integer",
+            Self::Boolean => "boolean",
+        }
+    }
+
+    fn matches(&self, value: &serde_json::Value) -> bool {
+        match self {
+            Self::String => value.is_string(),
+            Self::Integer => value.is_u64(),
+            Self::Boolean => value.is_boolean(),
+        }
+    }
+}
+
+/// all parameters are required to satisfy the strict mode of function calling
+pub struct Param {
+    pub name: &'static str,
+    pub kind: ParamType,
+    pub description: &'static str,
+}
+
+pub struct ToolContext<'a> {
+    pub community: &'a Community,
+    /// the LLM run calling the tool
+    pub run_id: &'a str,
+    /// the authors of the thread and the comment in the run
+    pub authors: Vec<AccountId>,
+}
+
+/// the arguments are checked against the params before calling the handler
+pub type Handler = fn(&ToolContext, &serde_json::Value) -> Result<String, String>;
+
+pub struct Tool {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub params: &'static [Param],
+    pub permission: Permission,
+    pub handler: Handler,
+}
+
+pub const REGISTRY: &[Tool] = &[
+    Tool {
+        name: "transfer",
+        description: "Transfer funds to another user",
+        params: &[
+            Param {
+                name: "recipient",
+                kind: ParamType::String,
+                description: "The recipient user_id",
+            },
+            Param {
+                name: "amount",
+                kind: ParamType::Integer,
+                description: "The amount to transfer",
+            },
+        ],
+        permission: Permission::Transfer,
+        handler: transfer,
+    },
+    Tool {
+        name: "agent_balance",
+        description: "Check the balance of the assistant",
+        params: &[],
+        permission: Permission::Read,
+        handler: agent_balance,
+    },
+    Tool {
+        name: "balance_of",
+        description: "Query the balance of a user",
+        params: &[Param {
+            name: "account_id",
+            kind: ParamType::String,
+            description: "The user_id to query",
+        }],
+        permission: Permission::Read,
+        handler: balance_of,
+    },
+    Tool {
+        name: "get_thread",
+        description: "Read a thread with its latest comments",
+        params: &[Param {
+            name: "thread_id",
+            kind: ParamType::String,
+            description: "The id of the thread",
+        }],
+        permission: Permission::Read,
+        handler: get_thread,
+    },
+    Tool {
+        name: "list_threads",
+        description: "List the recent threads of the community",
+        params: &[Param {
+            name: "limit",
+            kind: ParamType::Integer,
+            description: "The number of threads, no more than 20",
+        }],
+        permission: Permission::Read,
+        handler: list_threads,
+    },
+    Tool {
+        name: "get_user",
+        description: "Get the profile of a user and the activities in the community",
+        params: &[Param {
+            name: "account_id",
+            kind: ParamType::String,
+            description: "The user_id to query",
+        }],
+        permission: Permission::Read,
+        handler: get_user,
+    },
+    Tool {
+        name: "get_community_info",
+        description: "Get the description, the rules and the pinned threads of the community",
+        params: &[],
+        permission: Permission::Read,
+        handler: get_community_info,
+    },
+    Tool {
+        name: "hide_comment",
+        description: "Hide or unhide a comment in the community",
+        params: &[
+            Param {
+                name: "comment_id",
+                kind: ParamType::String,
+                description: "The id of the comment",
+            },
+            Param {
+                name: "hidden",
+                kind: ParamType::Boolean,
+                description: "Whether to hide the comment",
+            },
+        ],
+        permission: Permission::Moderate,
+        handler: hide_comment,
+    },
+    Tool {
+        name: "delete_comment",
+        description: "Delete a comment which violates the rules of the community",
+        params: &[Param {
+            name: "comment_id",
+            kind: ParamType::String,
+            description: "The id of the comment",
+        }],
+        permission: Permission::Moderate,
+        handler: delete_comment,
+    },
+    Tool {
+        name: "lock_thread",
+        description:
+            "Lock or unlock a thread, no one but the moderators could comment a locked thread",
+        params: &[
+            Param {
+                name: "thread_id",
+                kind: ParamType::String,
+                description: "The id of the thread",
+            },
+            Param {
+                name: "locked",
+                kind: ParamType::Boolean,
+                description: "Whether to lock the thread",
+            },
+        ],
+        permission: Permission::Moderate,
+        handler: lock_thread,
+    },
+    Tool {
+        name: "pin_thread",
+        description: "Pin or unpin a thread in the community",
+        params: &[
+            Param {
+                name: "thread_id",
+                kind: ParamType::String,
+                description: "The id of the thread",
+            },
+            Param {
+                name: "pinned",
+                kind: ParamType::Boolean,
+                description: "Whether to pin the thread",
+            },
+        ],
+        permission: Permission::Moderate,
+        handler: pin_thread,
+    },
+    Tool {
+        name: "mute_user",
+        description: "Forbid a user from posting in the community for a period",
+        params: &[
+            Param {
+                name: "account_id",
+                kind: ParamType::String,
+                description: "The user_id to mute",
+            },
+            Param {
+                name: "minutes",
+                kind: ParamType::Integer,
+                description: "The minutes to mute, no more than 7 days",
+            },
+        ],
+        permission: Permission::Moderate,
+        handler: mute_user,
+    },
+    Tool {
+        name: "tag_thread",
+        description: "Replace the tags of a thread",
+        params: &[
+            Param {
+                name: "thread_id",
+                kind: ParamType::String,
+                description: "The id of the thread",
+            },
+            Param {
+                name: "tags",
+                kind: ParamType::String,
+                description: "The tags separated by commas, no more than 5",
+            },
+        ],
+        permission: Permission::Moderate,
+        handler: tag_thread,
+    },
+];
+
+impl Tool {
+    /// the function tool of the OpenAI-compatible APIs
+    pub fn openai_schema(&self) -> serde_json::Value {
+        let properties = self
+            .params
+            .iter()
+            .map(|p| {
+                (
+                    p.name.to_string(),
+                    serde_json::json!({
+                        "type": p.kind.as_str(),
+                        "description": p.description,
+                    }),
+                )
+            })
+            .collect::<serde_json::Map<_, _>>();
+        let required = self.params.iter().map(|p| p.name).collect::<Vec<_>>();
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": {
+                    "type": "object",
+                    "properties": properties,
+                    "required": required,
+                    "additionalProperties": false
+                },
+                "strict": true
+            }
+        })
+    }
+
+    /// parse the arguments generated by the LLM
+    pub fn parse_args(&self, args: &str) -> Result<serde_json::Value, String> {
+        let args = if args.trim().is_empty() { "{}" } else { args };
+        let json: serde_json::Value =
+            serde_json::from_str(args).map_err(|_| "Invalid parameters".to_string())?;
+        let fields = json.as_object().ok_or("Invalid parameters".to_string())?;
+        for param in self.params {
+            let value = fields
+                .get(param.name)
+                .ok_or(format!("Missing param: {}", param.name))?;
+            param
+                .kind
+                .matches(value)
+                .then(|| ())
+                .ok_or(format!("Invalid param: {}", param.name))?;
+        }
+        fields
+            .keys()
+            .all(|k| self.params.iter().any(|p| p.name == k))
+            .then(|| ())
+            .ok_or("Unknown parameters".to_string())?;
+        Ok(json)
+    }
+}
+
+pub fn find(name: &str) -> Option<&'static Tool> {
+    REGISTRY.iter().find(|t| t.name == name)
+}
+
+pub fn default_tools() -> Vec<String> {
+    REGISTRY
+        .iter()
+        .filter(|t| t.permission.is_default())
+        .map(|t| t.name.to_string())
+        .collect()
+}
+
+fn enabled(community: &Community, name: &str) -> Option<&'static Tool> {
+    community
+        .agent_tools
+        .iter()
+        .any(|t| t == name)
+        .then(|| find(name))
+        .flatten()
+}
+
+/// the definitions of the tools enabled by the community
+pub fn definitions(community: &Community) -> serde_json::Value {
+    REGISTRY
+        .iter()
+        .filter(|t| enabled(community, t.name).is_some())
+        .map(|t| t.openai_schema())
+        .collect()
+}
+
+pub fn call(ctx: &ToolContext, name: &str, args: &str) -> Result<String, String> {
+    let tool = enabled(ctx.community, name).ok_or("The tool is not enabled".to_string())?;
+    let args = tool.parse_args(args)?;
+    (tool.handler)(ctx, &args).map(|output| bounded(output, MAX_OUTPUT_LEN))
+}
+
+/// cut the text at a char boundary
+pub(crate) fn bounded(mut text: String, max_len: usize) -> String {
+    if text.len() > max_len {
+        let mut end = max_len;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        text.push_str("...(truncated)");
+    }
+    text
+}
+
+pub(crate) fn excerpt(community: &Community, content: &[u8]) -> String {
+    let limit = community.content_rules().max_content_len as usize;
+    let text = crate::decompress(content, limit).unwrap_or_default();
+    match text.char_indices().nth(MAX_EXCERPT_CHARS) {
+        Some((end, _)) => format!("{}...(truncated)", &text[..end]),
+        None => text,
+    }
+}
+
+fn to_json(value: serde_json::Value) -> Result<String, String> {
+    serde_json::to_string(&value).map_err(|e| e.to_string())
+}
+
+pub(crate) fn thread_summary(thread: &Thread) -> serde_json::Value {
+    serde_json::json!({
+        "id": thread.id,
+        "title": thread.title,
+        "author": thread.author,
+        "tags": thread.tags,
+        "pinned": thread.pinned,
+        "locked": thread.locked,
+        "created_time": thread.created_time,
+    })
+}
+
+/// the newest threads of the community, the ids are allocated in sequence so that the threads
+/// posted before the feed index are found as well
+pub(crate) fn community_threads(
+    community_id: CommunityId,
+    limit: usize,
+) -> Result<Vec<Thread>, String> {
+    let base = (community_id as u128) << 64;
+    let prefix = trie::to_content_key(base);
+    let latest = storage::search(
+        &trie::to_content_key(base | u64::MAX as u128),
+        storage::Direction::Reverse,
+    )
+    .map_err(|e| e.to_string())?
+    .filter(|(k, _)| k.starts_with(&prefix[..8]))
+    .map(|(k, _)| trie::to_content_id(&k))
+    .transpose()?;
+    let mut index = latest.map(|id| (id >> 32) as u32).unwrap_or_default();
+    let mut r = vec![];
+    let mut scanned = 0;
+    while index > 0 && r.len() < limit && scanned < MAX_SCANNED_THREADS {
+        let id = base | ((index as u128) << 32);
+        if let Some(thread) = crate::find::<Thread>(&trie::to_content_key(id))? {
+            r.push(thread);
+        }
+        index -= 1;
+        scanned += 1;
+    }
+    Ok(r)
+}
+
+/// the newest threads indexed by the author or the community
+pub(crate) fn recent_threads(source: &Followee, limit: usize) -> Result<Vec<Thread>, String> {
+    let mut r = vec![];
+    for (_, id) in crate::feed::list_threads_of(source, None, limit)? {
+        if let Some(thread) = crate::find::<Thread>(&trie::to_content_key(id))? {
+            r.push(thread);
+        }
+    }
+    Ok(r)
+}
+
+/// the latest visible comments in time order
+fn latest_comments(thread_id: ContentId) -> Result<Vec<Comment>, String> {
+    let upper = trie::to_content_key(thread_id | u32::MAX as u128);
+    let result = storage::get_range(&upper, storage::Direction::Reverse, MAX_LISTED_COMMENTS * 2)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, v) in result.into_iter() {
+        if !k.starts_with(&upper[..12]) {
+            continue;
+        }
+        match trie::to_content_id(&k) {
+            Ok(id) if trie::is_comment(id) => {
+                let comment = Comment::decode(&mut &v[..]).map_err(|e| e.to_string())?;
+                if !comment.hidden {
+                    r.push(comment);
+                }
+            }
+            _ => {}
+        }
+        if r.len() == MAX_LISTED_COMMENTS {
+            break;
+        }
+    }
+    r.reverse();
+    Ok(r)
+}
+
+fn account_arg(args: &serde_json::Value, name: &str) -> Result<AccountId, String> {
+    let account = args[name]
+        .as_str()
+        .ok_or(format!("Invalid param: {}", name))?;
+    AccountId::from_str(account).map_err(|_| format!("Invalid param: {}", name))
+}
+
+/// the contents are identified by the hex of the encoded id
+fn content_arg(
+    ctx: &ToolContext,
+    args: &serde_json::Value,
+    name: &str,
+) -> Result<ContentId, String> {
+    let id = args[name]
+        .as_str()
+        .ok_or(format!("Invalid param: {}", name))?;
+    let id = hex::decode(id.trim_start_matches("0x"))
+        .ok()
+        .and_then(|v| ContentId::decode(&mut &v[..]).ok())
+        .ok_or(format!("Invalid param: {}", name))?;
+    (vemodel::get_belongs_to(id) == ctx.community.id())
+        .then(|| ())
+        .ok_or("The content is not in this community".to_string())?;
+    Ok(id)
+}
+
+fn moderated(id: u32) -> String {
+    format!("Ok, recorded as moderation {}", id)
+}
+
+fn transfer(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let community = ctx.community;
+    let recipient = account_arg(args, "recipient")?;
+    let amount = args["amount"].as_u64().ok_or("Invalid param: amount")? as u128;
+    crate::ensure_active(community)?;
+    super::treasury::ensure_allowed(community, &ctx.authors, recipient, amount)?;
+    crate::transfer(community.id(), community.agent_pubkey, recipient, amount)?;
+    super::treasury::record(community.id(), recipient, amount)?;
+    Ok("Ok".to_string())
+}
+
+fn agent_balance(ctx: &ToolContext, _args: &serde_json::Value) -> Result<String, String> {
+    crate::balance_of(ctx.community.id(), ctx.community.agent_pubkey)
+}
+
+fn balance_of(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let account = account_arg(args, "account_id")?;
+    crate::balance_of(ctx.community.id(), account)
+}
+
+fn get_thread(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let community = ctx.community;
+    let thread_id = content_arg(ctx, args, "thread_id")?;
+    (!trie::is_comment(thread_id))
+        .then(|| ())
+        .ok_or("Not a thread".to_string())?;
+    let thread = crate::find::<Thread>(&trie::to_content_key(thread_id))?
+        .ok_or("Thread not found".to_string())?;
+    thread_context(community, &thread)
+}
+
+fn comment_excerpt(community: &Community, comment: &Comment) -> serde_json::Value {
+    serde_json::json!({
+        "id": comment.id,
+        "author": comment.author,
+        "reply_to": comment.reply_to,
+        "content": excerpt(community, &comment.content),
+        "created_time": comment.created_time,
+    })
+}
+
+/// the thread with its latest comments which fit in the output budget
+pub(crate) fn thread_context(community: &Community, thread: &Thread) -> Result<String, String> {
+    let mut output = thread_summary(thread);
+    output["content"] = excerpt(community, &thread.content).into();
+    let mut budget = MAX_OUTPUT_LEN.saturating_sub(output.to_string().len());
+    let mut comments = vec![];
+    for comment in latest_comments(thread.id())?.into_iter().rev() {
+        let comment = comment_excerpt(community, &comment);
+        let len = comment.to_string().len();
+        if len > budget {
+            break;
+        }
+        budget -= len;
+        comments.push(comment);
+    }
+    comments.reverse();
+    output["comments"] = comments.into();
+    to_json(output)
+}
+
+/// the visible comments after `after` in time order which fit in the output budget, at least
+/// one is returned unless there is no more
+pub(crate) fn comments_after(
+    community: &Community,
+    thread_id: ContentId,
+    after: Option<ContentId>,
+) -> Result<(Vec<serde_json::Value>, Option<ContentId>), String> {
+    let prefix = trie::to_content_key(thread_id);
+    let mut start = after.unwrap_or(thread_id) + 1;
+    let mut budget = MAX_OUTPUT_LEN;
+    let mut comments = vec![];
+    let mut last = None;
+    loop {
+        let result = storage::get_range(
+            &trie::to_content_key(start),
+            storage::Direction::Forward,
+            MAX_LISTED_COMMENTS,
+        )
+        .map_err(|e| e.to_string())?;
+        let fetched = result.len();
+        for (k, v) in result.into_iter() {
+            let id = match trie::to_content_id(&k) {
+                Ok(id) if k.starts_with(&prefix[..12]) => id,
+                _ => return Ok((comments, last)),
+            };
+            let comment = Comment::decode(&mut &v[..]).map_err(|e| e.to_string())?;
+            if !comment.hidden {
+                let comment = comment_excerpt(community, &comment);
+                let len = comment.to_string().len();
+                if len > budget && !comments.is_empty() {
+                    return Ok((comments, last));
+                }
+                budget = budget.saturating_sub(len);
+                comments.push(comment);
+            }
+            last = Some(id);
+            start = id + 1;
+        }
+        if fetched < MAX_LISTED_COMMENTS {
+            return Ok((comments, last));
+        }
+    }
+}
+
+fn list_threads(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let limit = args["limit"].as_u64().ok_or("Invalid param: limit")?;
+    let limit = limit.clamp(1, MAX_LISTED_THREADS) as usize;
+    let threads = community_threads(ctx.community.id(), limit)?
+        .iter()
+        .map(thread_summary)
+        .collect::<Vec<_>>();
+    to_json(threads.into())
+}
+
+fn get_user(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let community_id = ctx.community.id();
+    let account_id = account_arg(args, "account_id")?;
+    let account = crate::get_account_info(account_id)?;
+    let stats = crate::ratelimit::get_post_stats(community_id, account_id)?;
+    let threads = recent_threads(&Followee::Account(account_id), MAX_SCANNED_THREADS)?
+        .into_iter()
+        .filter(|t| t.community_id() == community_id)
+        .collect::<Vec<_>>();
+    let output = serde_json::json!({
+        "user_id": account_id,
+        "alias": account.alias,
+        "bio": account.bio.map(|b| bounded(b, MAX_EXCERPT_CHARS)),
+        "language": account.language,
+        "reputation": crate::reputation::get_reputation(community_id, account_id)?,
+        "muted_until": crate::moderation::get_mute(community_id, account_id)?,
+        "threads_today": if stats.day == crate::usage::today() { stats.posts } else { 0 },
+        "comments_today": if stats.day == crate::usage::today() { stats.comments } else { 0 },
+        "thread_count": stats.total_threads,
+        "comment_count": stats.total_comments,
+        // counted from the latest threads of the user across all communities
+        "recent_thread_count": threads.len(),
+        "recent_threads": threads.iter().take(5).map(thread_summary).collect::<Vec<_>>(),
+    });
+    to_json(output)
+}
+
+fn get_community_info(ctx: &ToolContext, _args: &serde_json::Value) -> Result<String, String> {
+    let community = ctx.community;
+    let pinned = crate::moderation::pinned_threads(community.id(), MAX_LISTED_THREADS as usize)?
+        .into_iter()
+        .map(|t| {
+            let mut summary = thread_summary(&t);
+            summary["content"] = excerpt(community, &t.content).into();
+            summary
+        })
+        .collect::<Vec<_>>();
+    let output = serde_json::json!({
+        "name": community.name,
+        "description": bounded(community.description.clone(), MAX_EXCERPT_CHARS),
+        "mode": community.mode,
+        "creator": community.creator,
+        "min_post_reputation": community.min_post_reputation,
+        "rate_limit": community.rate_limit,
+        "content_rules": community.content_rules(),
+        "pinned_threads": pinned,
+    });
+    to_json(output)
+}
+
+fn hide_comment(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let comment = content_arg(ctx, args, "comment_id")?;
+    let hidden = args["hidden"].as_bool().ok_or("Invalid param: hidden")?;
+    crate::moderation::hide_comment(ctx.community.id(), ctx.run_id, comment, hidden).map(moderated)
+}
+
+fn delete_comment(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let comment = content_arg(ctx, args, "comment_id")?;
+    crate::moderation::delete_comment(ctx.community.id(), ctx.run_id, comment).map(moderated)
+}
+
+fn lock_thread(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let thread = content_arg(ctx, args, "thread_id")?;
+    let locked = args["locked"].as_bool().ok_or("Invalid param: locked")?;
+    crate::moderation::lock_thread(ctx.community.id(), ctx.run_id, thread, locked).map(moderated)
+}
+
+fn pin_thread(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let thread = content_arg(ctx, args, "thread_id")?;
+    let pinned = args["pinned"].as_bool().ok_or("Invalid param: pinned")?;
+    crate::moderation::pin_thread(ctx.community.id(), ctx.run_id, thread, pinned).map(moderated)
+}
+
+fn mute_user(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let account = account_arg(args, "account_id")?;
+    let minutes = args["minutes"].as_u64().ok_or("Invalid param: minutes")?;
+    crate::moderation::mute_user(ctx.community, ctx.run_id, account, minutes).map(moderated)
+}
+
+fn tag_thread(ctx: &ToolContext, args: &serde_json::Value) -> Result<String, String> {
+    let thread = content_arg(ctx, args, "thread_id")?;
+    let tags = args["tags"].as_str().ok_or("Invalid param: tags")?;
+    crate::moderation::tag_thread(ctx.community.id(), ctx.run_id, thread, tags).map(moderated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_are_unique() {
+        for (i, tool) in REGISTRY.iter().enumerate() {
+            assert!(REGISTRY[i + 1..].iter().all(|t| t.name != tool.name));
+        }
+    }
+
+    #[test]
+    fn schema_declares_all_params() {
+        for tool in REGISTRY {
+            let schema = tool.openai_schema();
+            assert_eq!(schema["function"]["name"], tool.name);
+            let parameters = &schema["function"]["parameters"];
+            let required = parameters["required"].as_array().unwrap();
+            assert_eq!(required.len(), tool.params.len());
+            for param in tool.params {
+                assert_eq!(
+                    parameters["properties"][param.name]["type"],
+                    param.kind.as_str()
+                );
+                assert!(required.iter().any(|r| r == param.name));
+            }
+        }
+    }
+
+    #[test]
+    fn balance_of_accepts_account_id() {
+        let tool = find("balance_of").unwrap();
+        let account = "0x0000000000000000000000000000000000000001";
+        let args = tool
+            .parse_args(&format!(r#"{{"account_id":"{}"}}"#, account))
+            .unwrap();
+        assert!(account_arg(&args, "account_id").is_ok());
+        assert!(tool
+            .parse_args(&format!(r#"{{"account":"{}"}}"#, account))
+            .is_err());
+    }
+
+    #[test]
+    fn parse_args_checks_types() {
+        let tool = find("transfer").unwrap();
+        let recipient = "0x0000000000000000000000000000000000000001";
+        assert!(tool
+            .parse_args(&format!(r#"{{"recipient":"{}","amount":10}}"#, recipient))
+            .is_ok());
+        assert!(tool
+            .parse_args(&format!(r#"{{"recipient":"{}","amount":"10"}}"#, recipient))
+            .is_err());
+        assert!(tool
+            .parse_args(&format!(r#"{{"recipient":"{}","amount":-1}}"#, recipient))
+            .is_err());
+        assert!(tool.parse_args(r#"{"amount":10}"#).is_err());
+    }
+
+    #[test]
+    fn parameterless_tool_accepts_empty_args() {
+        let tool = find("agent_balance").unwrap();
+        assert!(tool.parse_args("").is_ok());
+        assert!(tool.parse_args("{}").is_ok());
+        assert!(tool.parse_args(r#"{"extra":1}"#).is_err());
+    }
+
+    #[test]
+    fn default_tools_are_registered() {
+        let tools = default_tools();
+        assert!(tools.iter().all(|t| find(t).is_some()));
+        assert!(find("unknown").is_none());
+    }
+
+    #[test]
+    fn bounded_cuts_at_char_boundary() {
+        assert_eq!(bounded("abc".to_string(), 3), "abc");
+        assert_eq!(bounded("abcd".to_string(), 3), "abc...(truncated)");
+        assert_eq!(bounded("你好".to_string(), 4), "你...(truncated)");
+    }
+
+    #[test]
+    fn moderation_is_opt_in() {
+        let tools = default_tools();
+        for tool in REGISTRY
+            .iter()
+            .filter(|t| t.permission == Permission::Moderate)
+        {
+            assert!(!tools.iter().any(|t| t == tool.name));
+        }
+    }
+
+    #[test]
+    fn parse_args_checks_booleans() {
+        let tool = find("lock_thread").unwrap();
+        assert!(tool
+            .parse_args(r#"{"thread_id":"00","locked":true}"#)
+            .is_ok());
+        assert!(tool
+            .parse_args(r#"{"thread_id":"00","locked":"true"}"#)
+            .is_err());
+    }
+}
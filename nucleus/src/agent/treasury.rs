@@ -0,0 +1,78 @@
+use crate::trie;
+use vemodel::*;
+
+/// the counters are reset on a new day
+pub(crate) fn get_transfer_stats(community_id: CommunityId) -> Result<TransferStats, String> {
+    let today = crate::usage::today();
+    let stats = crate::find::<TransferStats>(&trie::to_transfer_stats_key(community_id))?
+        .filter(|s| s.day == today)
+        .unwrap_or(TransferStats {
+            day: today,
+            ..Default::default()
+        });
+    Ok(stats)
+}
+
+/// check the transfer against the policy of the community, the violations are logged
+pub(crate) fn ensure_allowed(
+    community: &Community,
+    authors: &[AccountId],
+    recipient: AccountId,
+    amount: u128,
+) -> Result<(), String> {
+    check(community, authors, recipient, amount).inspect_err(|e| {
+        vrs_core_sdk::println!(
+            "transfer of {} to {} in {} is rejected: {}",
+            amount,
+            recipient,
+            community.name,
+            e
+        )
+    })
+}
+
+fn check(
+    community: &Community,
+    authors: &[AccountId],
+    recipient: AccountId,
+    amount: u128,
+) -> Result<(), String> {
+    let policy = &community.transfer_policy;
+    (!policy.block_self_dealing || !authors.contains(&recipient))
+        .then(|| ())
+        .ok_or("Transferring to the author of this thread or comment is not allowed".to_string())?;
+    if let Some(max) = policy.max_per_transfer {
+        (amount <= max)
+            .then(|| ())
+            .ok_or(format!("A single transfer should be no more than {}", max))?;
+    }
+    let stats = get_transfer_stats(community.id())?;
+    if let Some(max) = policy.max_per_recipient_daily {
+        (stats.received(&recipient).saturating_add(amount) <= max)
+            .then(|| ())
+            .ok_or(format!(
+                "The recipient could receive no more than {} per day",
+                max
+            ))?;
+    }
+    if let Some(max) = policy.max_daily_total {
+        (stats.total.saturating_add(amount) <= max)
+            .then(|| ())
+            .ok_or(format!("No more than {} could be transferred per day", max))?;
+    }
+    Ok(())
+}
+
+pub(crate) fn record(
+    community_id: CommunityId,
+    recipient: AccountId,
+    amount: u128,
+) -> Result<(), String> {
+    let mut stats = get_transfer_stats(community_id)?;
+    stats.total = stats.total.saturating_add(amount);
+    match stats.recipients.iter_mut().find(|(a, _)| *a == recipient) {
+        Some((_, v)) => *v = v.saturating_add(amount),
+        None => stats.recipients.push((recipient, amount)),
+    }
+    crate::save(&trie::to_transfer_stats_key(community_id), &stats)
+}
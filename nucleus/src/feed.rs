@@ -0,0 +1,161 @@
+use crate::trie;
+use vemodel::*;
+use vrs_core_sdk::{codec::Decode, storage};
+
+/// every followed source costs a range scan per feed page, keep it small
+pub const MAX_FOLLOWING: usize = 100;
+pub const FEED_PAGE_SIZE: usize = 20;
+pub const MAX_BACKFILL_BATCH: usize = 1000;
+
+pub(crate) fn follow(follower: AccountId, followee: Followee, now: u64) -> Result<(), String> {
+    let key = trie::to_following_key(follower, &followee);
+    if crate::find::<u64>(&key)?.is_some() {
+        return Ok(());
+    }
+    (list_following(follower, None, MAX_FOLLOWING)?.len() < MAX_FOLLOWING)
+        .then(|| ())
+        .ok_or(format!("You can't follow more than {}", MAX_FOLLOWING))?;
+    crate::save(&key, &now)?;
+    crate::save(&trie::to_follower_key(&followee, follower), &now)
+}
+
+pub(crate) fn unfollow(follower: AccountId, followee: Followee) -> Result<(), String> {
+    storage::del(&trie::to_following_key(follower, &followee)).map_err(|e| e.to_string())?;
+    storage::del(&trie::to_follower_key(&followee, follower)).map_err(|e| e.to_string())
+}
+
+pub(crate) fn list_following(
+    account_id: AccountId,
+    gt: Option<Followee>,
+    limit: usize,
+) -> Result<Vec<Followee>, String> {
+    let prefix = [
+        &trie::FOLLOWING_KEY_PREFIX.to_be_bytes()[..],
+        &account_id.0[..],
+    ]
+    .concat();
+    let start = match gt {
+        Some(ref f) => trie::to_following_key(account_id, f).to_vec(),
+        None => prefix.clone(),
+    };
+    let result = storage::get_range(&start, storage::Direction::Forward, limit + 1)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, _) in result.into_iter() {
+        if k.len() != 49 || !k.starts_with(&prefix) || k == start {
+            continue;
+        }
+        r.push(trie::to_followee(&k[28..])?);
+    }
+    r.truncate(limit);
+    Ok(r)
+}
+
+pub(crate) fn list_followers(
+    followee: Followee,
+    gt: Option<AccountId>,
+    limit: usize,
+) -> Result<Vec<AccountId>, String> {
+    let prefix = [
+        &trie::FOLLOWER_KEY_PREFIX.to_be_bytes()[..],
+        &trie::to_followee_bytes(&followee)[..],
+    ]
+    .concat();
+    let start = match gt {
+        Some(account_id) => trie::to_follower_key(&followee, account_id).to_vec(),
+        None => prefix.clone(),
+    };
+    let result = storage::get_range(&start, storage::Direction::Forward, limit + 1)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, _) in result.into_iter() {
+        if k.len() != 49 || !k.starts_with(&prefix) || k == start {
+            continue;
+        }
+        r.push(AccountId::from_slice(&k[29..])?);
+    }
+    r.truncate(limit);
+    Ok(r)
+}
+
+/// index a new thread by its author and its community so that feeds could be merged by time
+pub(crate) fn index_thread(thread: &Thread) -> Result<(), String> {
+    let id = thread.id();
+    let created_time = thread.created_time as u64;
+    for source in [
+        Followee::Community(thread.community_id()),
+        Followee::Account(thread.author),
+    ] {
+        let key = trie::to_thread_index_key(&source, created_time, id);
+        crate::save(&key, &id)?;
+    }
+    Ok(())
+}
+
+/// index the threads posted before the feed index existed, starting from `from`;
+/// return the content id to continue from, or `None` when all contents are scanned
+pub(crate) fn backfill(from: ContentId, limit: usize) -> Result<Option<ContentId>, String> {
+    let result = storage::get_range(
+        &trie::to_content_key(from),
+        storage::Direction::Forward,
+        limit,
+    )
+    .map_err(|e| e.to_string())?;
+    let scanned = result.len();
+    let mut next = None;
+    for (k, v) in result.into_iter() {
+        let Ok(id) = trie::to_content_id(&k) else {
+            return Ok(None);
+        };
+        if !trie::is_comment(id) {
+            let thread = Thread::decode(&mut &v[..]).map_err(|e| e.to_string())?;
+            index_thread(&thread)?;
+        }
+        next = id.checked_add(1);
+    }
+    Ok(if scanned < limit { None } else { next })
+}
+
+/// list the newest threads of a single source, older than the cursor if present
+pub(crate) fn list_threads_of(
+    source: &Followee,
+    cursor: Option<FeedCursor>,
+    limit: usize,
+) -> Result<Vec<(u64, ContentId)>, String> {
+    let prefix = trie::to_thread_index_prefix(source);
+    let upper = match cursor {
+        Some(c) => trie::to_thread_index_key(source, c.created_time as u64, c.thread),
+        None => [&prefix[..], &[0xffu8; 24][..]].concat(),
+    };
+    let result = storage::get_range(&upper, storage::Direction::Reverse, limit + 1)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, _) in result.into_iter() {
+        if !k.starts_with(&prefix) || k.len() != prefix.len() + 24 || k >= upper {
+            continue;
+        }
+        r.push(trie::to_thread_index(&k)?);
+    }
+    r.truncate(limit);
+    Ok(r)
+}
+
+pub(crate) fn get_feed(
+    account_id: AccountId,
+    cursor: Option<FeedCursor>,
+) -> Result<Vec<Thread>, String> {
+    let mut candidates = vec![];
+    for source in list_following(account_id, None, MAX_FOLLOWING)? {
+        candidates.extend(list_threads_of(&source, cursor, FEED_PAGE_SIZE)?);
+    }
+    candidates.sort_by(|a, b| b.cmp(a));
+    candidates.dedup();
+    candidates.truncate(FEED_PAGE_SIZE);
+    let mut r = vec![];
+    for (_, id) in candidates {
+        if let Some(thread) = crate::find::<Thread>(&trie::to_content_key(id))? {
+            r.push(thread);
+        }
+    }
+    Ok(r)
+}
@@ -2,60 +2,198 @@ extern crate core;
 
 mod agent;
 pub mod eth_types;
+mod feed;
+mod message;
+mod moderation;
 mod nucleus;
+mod ratelimit;
+mod reputation;
 mod trie;
+mod usage;
 
 use crate::agent::rewards::generate_rewards;
 use crate::eth_types::Address;
 use crate::trie::{to_account_key, to_reward_payload_key, PERMISSION_KEY_PREFIX};
 use sha2::{Digest, Sha256};
 use vemodel::{
-    Account, AccountData, AccountId, Community, CommunityId, ContentId, Event, EventId, LlmVendor,
-    RewardPayload,
+    Account, AccountData, AccountId, Community, CommunityId, CommunityStatus, ContentId,
+    ContentRules, Event, EventId, GenerationParams, LlmVendor, ModelPrice, PlatformConfig,
+    PromptRevision, RewardPayload,
 };
 use vrs_core_sdk::{
     codec::{Decode, Encode},
     storage,
 };
 
-pub const MIN_ACTIVATE_FEE: u128 = 2_000_000_000_000_000;
-pub const MIN_INVITE_FEE: u128 = 2_000_000_000_000_000;
+pub(crate) fn get_platform_config() -> Result<PlatformConfig, String> {
+    crate::find::<PlatformConfig>(&trie::PLATFORM_CONFIG_KEY.to_be_bytes())
+        .map(|v| v.unwrap_or_default())
+}
 
+/// the platform key is only sent to the default host of the vendor
 pub(crate) fn from_llm_settings(
     llm_name: String,
     llm_api_host: Option<String>,
     llm_key: Option<String>,
 ) -> Result<LlmVendor, String> {
-    match llm_name.as_ref() {
-        "OpenAI" => {
-            if llm_key.is_none() {
-                let default_key = crate::agent::get_sys_key(crate::agent::OPENAI)?;
-                Ok(LlmVendor::OpenAI { key: default_key })
-            } else {
-                Ok(LlmVendor::OpenAI {
-                    key: llm_key.unwrap(),
-                })
-            }
+    let (vendor, default_host) = match llm_name.as_ref() {
+        "OpenAI" => (crate::agent::OPENAI, crate::agent::OPENAI_API_HOST),
+        "DeepSeek" => (crate::agent::DEEPSEEK, crate::agent::DEEPSEEK_API_HOST),
+        _ => return Err("unsupported LLM vendor".to_string()),
+    };
+    let host = match llm_api_host {
+        Some(host) => {
+            validate_llm_host(&host)?;
+            host.trim_end_matches('/').to_string()
         }
-        "DeepSeek" => {
-            false
+        None => default_host.to_string(),
+    };
+    let key = match llm_key {
+        Some(key) => key,
+        None => {
+            (host == default_host)
                 .then(|| ())
-                .ok_or("DeepSeek is not supported".to_string())?;
-            if llm_key.is_none() {
-                let default_key = crate::agent::get_sys_key(crate::agent::DEEPSEEK)?;
-                Ok(LlmVendor::DeepSeek {
-                    key: default_key,
-                    host: crate::agent::DEEPSEEK_API_HOST.to_string(),
-                })
-            } else {
-                Ok(LlmVendor::DeepSeek {
-                    key: llm_key.unwrap(),
-                    host: llm_api_host.unwrap_or(crate::agent::DEEPSEEK_API_HOST.to_string()),
-                })
-            }
+                .ok_or("The LLM key is required by the custom api host".to_string())?;
+            crate::agent::get_sys_key(vendor)?
+        }
+    };
+    if vendor == crate::agent::OPENAI {
+        Ok(LlmVendor::OpenAI { key, host })
+    } else {
+        Ok(LlmVendor::DeepSeek { key, host })
+    }
+}
+
+/// the node itself or its private network
+fn is_internal_host(name: &str) -> bool {
+    let name = name.to_ascii_lowercase();
+    if name == "localhost" || name.ends_with(".localhost") {
+        return true;
+    }
+    match name.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V4(ip)) => {
+            ip.is_loopback() || ip.is_private() || ip.is_link_local() || ip.is_unspecified()
+        }
+        Ok(std::net::IpAddr::V6(ip)) => {
+            let prefix = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || prefix & 0xfe00 == 0xfc00
+                || prefix & 0xffc0 == 0xfe80
+        }
+        Err(_) => false,
+    }
+}
+
+/// a public https host is required, the internal hosts are allowed only with the `local-llm`
+/// feature, e.g. the local stub servers for testing
+fn validate_llm_host(host: &str) -> Result<(), String> {
+    let (secure, rest) = match host.strip_prefix("https://") {
+        Some(rest) => (true, rest),
+        None => (
+            false,
+            host.strip_prefix("http://")
+                .ok_or("The LLM api host should be a http(s) url".to_string())?,
+        ),
+    };
+    let authority = rest.split(['/', '?', '#']).next().unwrap_or_default();
+    (!authority.is_empty() && !authority.contains('@') && !host.contains(char::is_whitespace))
+        .then(|| ())
+        .ok_or("Invalid LLM api host".to_string())?;
+    let name = match authority.strip_prefix('[') {
+        Some(v6) => v6.split(']').next().unwrap_or_default(),
+        None => authority.split(':').next().unwrap_or_default(),
+    };
+    if is_internal_host(name) {
+        return cfg!(feature = "local-llm")
+            .then(|| ())
+            .ok_or("The LLM api host should be public".to_string());
+    }
+    secure
+        .then(|| ())
+        .ok_or("The LLM api host should be https".to_string())
+}
+
+/// the default models are allowed until the admins set the list
+pub(crate) fn get_models() -> Result<Vec<ModelPrice>, String> {
+    let models = crate::find::<Vec<ModelPrice>>(&trie::MODELS_KEY.to_be_bytes())?;
+    Ok(models.unwrap_or_else(|| {
+        vec![
+            ModelPrice {
+                vendor: "OpenAI".to_string(),
+                model: crate::agent::OPENAI_MODEL.to_string(),
+                prompt_price: 2_500_000,
+                completion_price: 10_000_000,
+            },
+            ModelPrice {
+                vendor: "DeepSeek".to_string(),
+                model: crate::agent::DEEPSEEK_MODEL.to_string(),
+                prompt_price: 270_000,
+                completion_price: 1_100_000,
+            },
+        ]
+    }))
+}
+
+/// the models served by the platform hosts must be in the allowlist,
+/// the self-hosted servers are free to choose
+pub(crate) fn ensure_model_allowed(
+    vendor: &LlmVendor,
+    generation: &GenerationParams,
+) -> Result<(), String> {
+    generation.validate()?;
+    if !crate::agent::is_platform_host(vendor) {
+        return Ok(());
+    }
+    let model = crate::agent::model_of(vendor, generation);
+    crate::get_models()?
+        .iter()
+        .any(|m| m.vendor == vendor.name() && m.model == model)
+        .then(|| ())
+        .ok_or(format!("The model {} is not allowed", model))
+}
+
+/// keep the replaced prompt in the history
+pub(crate) fn save_prompt_revision(community: &Community) -> Result<(), String> {
+    let community_id = community.id();
+    let end_key = trie::to_prompt_history_key(community_id, u32::MAX);
+    let seq = storage::search(&end_key, storage::Direction::Reverse)
+        .map_err(|e| e.to_string())?
+        .filter(|(k, _)| k.len() == 16 && k.starts_with(&end_key[..12]))
+        .map(|(k, _)| u32::from_be_bytes(k[12..].try_into().expect("qed")) + 1)
+        .unwrap_or(0);
+    let revision = PromptRevision {
+        prompt: community.prompt.clone(),
+        replaced_time: vrs_core_sdk::timer::now() as i64,
+    };
+    crate::save(&trie::to_prompt_history_key(community_id, seq), &revision)
+}
+
+/// list the replaced prompts with their sequences, the latest first
+pub(crate) fn get_prompt_history(
+    community_id: CommunityId,
+    before: Option<u32>,
+    limit: usize,
+) -> Result<Vec<(u32, PromptRevision)>, String> {
+    let upper = trie::to_prompt_history_key(community_id, before.unwrap_or(u32::MAX));
+    let result = storage::get_range(&upper, storage::Direction::Reverse, limit + 1)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, v) in result.into_iter() {
+        if k.len() != 16 || !k.starts_with(&upper[..12]) {
+            continue;
         }
-        _ => Err("unsupported LLM vendor".to_string()),
+        let seq = u32::from_be_bytes(k[12..].try_into().expect("qed"));
+        if before.is_some_and(|b| seq >= b) {
+            continue;
+        }
+        r.push((
+            seq,
+            PromptRevision::decode(&mut &v[..]).map_err(|e| e.to_string())?,
+        ));
     }
+    r.truncate(limit);
+    Ok(r)
 }
 
 pub(crate) fn try_find_community(community_id: CommunityId) -> Result<Community, String> {
@@ -197,7 +335,7 @@ pub(crate) fn get_nonce(account_id: AccountId) -> Result<u64, String> {
     }
 }
 
-pub(crate) fn incr_nonce(account_id: AccountId, update_time: Option<u64>) -> Result<(), String> {
+pub(crate) fn incr_nonce(account_id: AccountId) -> Result<(), String> {
     let key = trie::to_account_key(account_id);
     let mut account = match crate::find::<AccountData>(&key)? {
         Some(AccountData::Pubkey(data)) => Ok(data),
@@ -205,13 +343,11 @@ pub(crate) fn incr_nonce(account_id: AccountId, update_time: Option<u64>) -> Res
         None => Ok(Account::new(account_id)),
     }?;
     account.nonce += 1;
-    if let Some(t) = update_time {
-        account.last_post_at = t;
-    }
     storage::put(&key, AccountData::Pubkey(account).encode()).map_err(|e| e.to_string())?;
     Ok(())
 }
 
+/// the callers check the community is active, the refunds are still allowed after frozen
 pub(crate) fn transfer(
     community_id: CommunityId,
     from: AccountId,
@@ -219,6 +355,7 @@ pub(crate) fn transfer(
     amount: u128,
 ) -> Result<(), String> {
     let community = crate::try_find_community(community_id)?;
+    let tokens = amount;
     let amount = amount * 10u128.pow(community.token_info.decimals as u32);
     let from_key = trie::to_balance_key(community_id.clone(), from);
     let from_balance = storage::get(&from_key)
@@ -239,6 +376,13 @@ pub(crate) fn transfer(
     storage::put(&from_key, (from_balance - amount).encode()).map_err(|e| e.to_string())?;
     storage::put(&to_key, (to_balance + amount).encode()).map_err(|e| e.to_string())?;
     let community = crate::try_find_community(community_id)?;
+    if from == community.agent_pubkey {
+        crate::reputation::contribute(
+            community_id,
+            to,
+            crate::reputation::Contribution::Reward(tokens),
+        )?;
+    }
     if let Some(reward) = generate_rewards(Address::from(to.0.clone()), amount as u128, &community)
     {
         let key = to_reward_payload_key(community_id, to.clone());
@@ -269,25 +413,101 @@ pub(crate) fn into_account_id(alias: &str) -> AccountId {
     AccountId::from_arbitrary(alias.as_bytes())
 }
 
-pub(crate) fn decompress(data: &[u8]) -> Result<String, String> {
-    use std::io::Read;
-    let mut decoder = flate2::read::GzDecoder::new(data);
-    let mut s = String::new();
-    decoder
-        .read_to_string(&mut s)
-        .map_err(|e| format!("Invalid compressed data: {:?}", e))?;
-    Ok(s)
+/// decompress no more than `limit` bytes to avoid zip bombs
+pub(crate) fn decompress(data: &[u8], limit: usize) -> Result<String, String> {
+    vemodel::codec::decode(data, limit).map(|(_, text)| text)
 }
 
+/// return the digest of a post if it isn't the exact duplicate of the author's last post in the
+/// same scope, see `trie::to_last_post_key`
+pub(crate) fn check_duplicate(
+    account_id: AccountId,
+    scope: ContentId,
+    rules: &ContentRules,
+    title: &str,
+    text: &str,
+) -> Result<[u8; 32], String> {
+    let mut hasher = Sha256::new();
+    hasher.update(title.as_bytes());
+    hasher.update([0u8]);
+    hasher.update(text.as_bytes());
+    let digest: [u8; 32] = hasher.finalize().into();
+    if rules.reject_duplicate {
+        let last = crate::find::<[u8; 32]>(&trie::to_last_post_key(account_id, scope))?;
+        (last != Some(digest))
+            .then(|| ())
+            .ok_or("The post is the same as your last one here".to_string())?;
+    }
+    Ok(digest)
+}
+
+/// the replies of agents are markdown
 pub(crate) fn compress(data: &str) -> Result<Vec<u8>, String> {
-    use std::io::Write;
-    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
-    encoder
-        .write_all(data.as_bytes())
-        .map_err(|e| format!("Invalid data: {:?}", e))?;
-    encoder
-        .finish()
-        .map_err(|e| format!("Invalid compressed data: {:?}", e))
+    vemodel::codec::encode(
+        data,
+        vemodel::ContentFormat::Markdown,
+        vemodel::Compression::Gzip,
+    )
+}
+
+pub(crate) fn ensure_active(community: &Community) -> Result<(), String> {
+    match &community.status {
+        CommunityStatus::Active => Ok(()),
+        CommunityStatus::Frozen(until, reason) => Err(format!(
+            "The community is frozen until {}: {}",
+            until, reason
+        )),
+        _ => Err("The community is inactive.".to_string()),
+    }
+}
+
+/// the max delay of a timer accepted by the runtime
+pub(crate) const MAX_TIMER_DELAY: u64 = 365 * 24 * 60 * 60;
+
+/// the genesis admins, separated by comma, recorded at `#[init]` or by `bootstrap_admins`
+pub const GENESIS_ADMINS: Option<&str> = option_env!("VEFORUM_ADMINS");
+
+/// save the valid genesis admins and return how many are saved
+pub(crate) fn seed_admins() -> Result<usize, String> {
+    let mut admins = 0;
+    for admin in GENESIS_ADMINS
+        .unwrap_or_default()
+        .split(',')
+        .filter(|s| !s.trim().is_empty())
+    {
+        match admin.trim().parse::<AccountId>() {
+            Ok(account_id) => {
+                crate::save(&trie::to_admin_key(account_id), &1u32)?;
+                admins += 1;
+            }
+            Err(_) => vrs_core_sdk::println!("invalid admin: {}", admin),
+        }
+    }
+    Ok(admins)
+}
+
+pub(crate) fn is_admin(account_id: AccountId) -> bool {
+    crate::find::<u32>(&trie::to_admin_key(account_id))
+        .ok()
+        .flatten()
+        .is_some()
+}
+
+pub(crate) fn get_admins() -> Result<Vec<AccountId>, String> {
+    let prefix = trie::ADMIN_KEY_PREFIX.to_be_bytes();
+    let result =
+        storage::get_range(&prefix, storage::Direction::Forward, 100).map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, _) in result.into_iter() {
+        if k.len() == 28 && k.starts_with(&prefix) {
+            r.push(AccountId::from_slice(&k[8..])?);
+        }
+    }
+    Ok(r)
+}
+
+pub(crate) fn is_moderator(community: &Community, account_id: AccountId) -> bool {
+    community.creator == account_id || is_admin(account_id)
 }
 
 pub(crate) fn validate_write_permission(
@@ -0,0 +1,116 @@
+use crate::trie;
+use vemodel::{args::Args, crypto::*, *};
+use vrs_core_sdk::{
+    codec::{Decode, Encode},
+    storage, timer,
+};
+
+const MAX_QUERY_TTL_SECS: u64 = 600;
+
+/// store the uncompressed(with 0x04 prefix) secp256k1 public key recovered from a signature
+pub(crate) fn save_pubkey(account_id: AccountId, raw: [u8; 64]) -> Result<(), String> {
+    let pubkey = [&[0x04u8][..], &raw[..]].concat();
+    crate::save(&trie::to_pubkey_key(account_id), &pubkey)
+}
+
+pub(crate) fn get_pubkey(account_id: AccountId) -> Result<Option<Vec<u8>>, String> {
+    crate::find::<Vec<u8>>(&trie::to_pubkey_key(account_id))
+}
+
+/// a signed query is valid with the current nonce until `expire_at`, which can't be too far away
+pub(crate) fn ensure_query_signed<T: Encode>(
+    args: &Args<T, EcdsaSignature>,
+    expire_at: u64,
+) -> Result<(), String> {
+    let now = timer::now();
+    (now <= expire_at && expire_at <= now + MAX_QUERY_TTL_SECS)
+        .then(|| ())
+        .ok_or("The query should expire in 10 minutes".to_string())?;
+    args.ensure_signed(crate::get_nonce(args.signer)?)
+}
+
+fn allocate_message_id(conversation: ConversationId) -> Result<u64, String> {
+    let end_key = trie::to_message_key(conversation, u64::MAX);
+    let r = storage::search(&end_key, storage::Direction::Reverse)
+        .map_err(|e| e.to_string())?
+        .filter(|(k, _)| k.len() == 32 && k.starts_with(&end_key[..24]))
+        .map(|(k, _)| u64::from_be_bytes(k[24..].try_into().expect("qed")))
+        .unwrap_or(0);
+    (r < u64::MAX)
+        .then(|| ())
+        .ok_or("Too many messages in this conversation".to_string())?;
+    Ok(r + 1)
+}
+
+pub(crate) fn send(
+    sender: AccountId,
+    recipient: AccountId,
+    ciphertext: Vec<u8>,
+    sender_ciphertext: Vec<u8>,
+    now: i64,
+) -> Result<u64, String> {
+    let conversation = conversation_id(sender, recipient);
+    let id = allocate_message_id(conversation)?;
+    let message = DirectMessage {
+        id,
+        conversation,
+        sender,
+        recipient,
+        ciphertext,
+        sender_ciphertext,
+        created_time: now,
+    };
+    crate::save(&trie::to_message_key(conversation, id), &message)?;
+    for (account_id, peer) in [(sender, recipient), (recipient, sender)] {
+        let summary = Conversation {
+            id: conversation,
+            peer,
+            last_message_id: id,
+            updated_time: now,
+        };
+        crate::save(
+            &trie::to_conversation_key(account_id, conversation),
+            &summary,
+        )?;
+    }
+    Ok(id)
+}
+
+/// list messages of a conversation from the newest, older than `before` if present
+pub(crate) fn get_conversation(
+    conversation: ConversationId,
+    before: Option<u64>,
+    limit: usize,
+) -> Result<Vec<DirectMessage>, String> {
+    let start = trie::to_message_key(conversation, before.unwrap_or(u64::MAX));
+    let result = storage::get_range(&start, storage::Direction::Reverse, limit + 1)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, v) in result.into_iter() {
+        if k.len() != 32 || !k.starts_with(&start[..24]) || (before.is_some() && k == start) {
+            continue;
+        }
+        r.push(DirectMessage::decode(&mut &v[..]).map_err(|e| e.to_string())?);
+    }
+    r.truncate(limit);
+    Ok(r)
+}
+
+pub(crate) fn list_conversations(
+    account_id: AccountId,
+    gt: Option<ConversationId>,
+    limit: usize,
+) -> Result<Vec<Conversation>, String> {
+    let start = trie::to_conversation_key(account_id, gt.unwrap_or_default());
+    let result = storage::get_range(&start, storage::Direction::Forward, limit + 1)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, v) in result.into_iter() {
+        if k.len() != 44 || !k.starts_with(&start[..28]) || (gt.is_some() && k == start) {
+            continue;
+        }
+        r.push(Conversation::decode(&mut &v[..]).map_err(|e| e.to_string())?);
+    }
+    r.truncate(limit);
+    Ok(r)
+}
@@ -0,0 +1,385 @@
+use crate::trie;
+use vemodel::*;
+use vrs_core_sdk::{codec::Decode, storage, timer};
+
+const MAX_MUTE_MINUTES: u64 = 7 * 24 * 60;
+const MAX_TAGS: usize = 5;
+const MAX_TAG_LEN: usize = 32;
+
+pub(crate) fn get_mute(
+    community_id: CommunityId,
+    account_id: AccountId,
+) -> Result<Option<u64>, String> {
+    crate::find::<u64>(&trie::to_mute_key(community_id, account_id))
+}
+
+pub(crate) fn ensure_not_muted(
+    community_id: CommunityId,
+    account_id: AccountId,
+    now: u64,
+) -> Result<(), String> {
+    match get_mute(community_id, account_id)? {
+        Some(until) if until > now => Err("You're muted in this community".to_string()),
+        _ => Ok(()),
+    }
+}
+
+fn set_mute(
+    community_id: CommunityId,
+    account_id: AccountId,
+    until: Option<u64>,
+) -> Result<(), String> {
+    let key = trie::to_mute_key(community_id, account_id);
+    match until {
+        Some(until) => crate::save(&key, &until),
+        None => storage::del(&key).map_err(|e| e.to_string()),
+    }
+}
+
+fn find_thread(thread_id: ContentId) -> Result<Thread, String> {
+    (!trie::is_comment(thread_id))
+        .then(|| ())
+        .ok_or("Not a thread".to_string())?;
+    crate::find::<Thread>(&trie::to_content_key(thread_id))?.ok_or("Thread not found".to_string())
+}
+
+fn find_comment(comment_id: ContentId) -> Result<Comment, String> {
+    trie::is_comment(comment_id)
+        .then(|| ())
+        .ok_or("Not a comment".to_string())?;
+    crate::find::<Comment>(&trie::to_content_key(comment_id))?
+        .ok_or("Comment not found".to_string())
+}
+
+/// the pinned threads are indexed so that the old ones could be found
+fn update_thread(thread_id: ContentId, thread: &Thread) -> Result<(), String> {
+    let pinned_key = trie::to_pinned_key(thread.community_id(), thread_id);
+    if thread.pinned {
+        crate::save(&pinned_key, &1u32)?;
+    } else {
+        storage::del(&pinned_key).map_err(|e| e.to_string())?;
+    }
+    crate::save(&trie::to_content_key(thread_id), thread)?;
+    crate::save_event(Event::ThreadUpdated(thread_id))
+}
+
+fn update_comment(comment_id: ContentId, comment: &Comment) -> Result<(), String> {
+    crate::save(&trie::to_content_key(comment_id), comment)?;
+    crate::save_event(Event::CommentUpdated(comment_id))
+}
+
+fn allocate_record_id(community_id: CommunityId) -> Result<u32, String> {
+    let end_key = trie::to_moderation_key(community_id, u32::MAX);
+    let id = storage::search(&end_key, storage::Direction::Reverse)
+        .map_err(|e| e.to_string())?
+        .filter(|(k, _)| k.len() == 16 && k.starts_with(&end_key[..12]))
+        .map(|(k, _)| u32::from_be_bytes(k[12..].try_into().expect("qed")) + 1)
+        .unwrap_or(0);
+    Ok(id)
+}
+
+fn record(
+    community_id: CommunityId,
+    run_id: &str,
+    action: ModerationAction,
+) -> Result<u32, String> {
+    let id = allocate_record_id(community_id)?;
+    let record = ModerationRecord {
+        id,
+        action,
+        run_id: run_id.to_string(),
+        created_time: timer::now() as i64,
+        reverted: false,
+    };
+    crate::save(&trie::to_moderation_key(community_id, id), &record)?;
+    Ok(id)
+}
+
+/// the records from `from`, the deleted contents are masked
+pub(crate) fn get_log(
+    community_id: CommunityId,
+    from: u32,
+    limit: u32,
+) -> Result<Vec<ModerationRecord>, String> {
+    let start_key = trie::to_moderation_key(community_id, from);
+    let result = storage::get_range(
+        &start_key,
+        storage::Direction::Forward,
+        limit.min(100) as usize,
+    )
+    .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, v) in result.into_iter() {
+        if k.len() == 16 && k.starts_with(&start_key[..12]) {
+            let mut record = ModerationRecord::decode(&mut &v[..]).map_err(|e| e.to_string())?;
+            record.mask();
+            r.push(record);
+        }
+    }
+    Ok(r)
+}
+
+pub(crate) fn hide_comment(
+    community_id: CommunityId,
+    run_id: &str,
+    comment_id: ContentId,
+    hidden: bool,
+) -> Result<u32, String> {
+    let mut comment = find_comment(comment_id)?;
+    let previous = comment.hidden;
+    comment.hidden = hidden;
+    update_comment(comment_id, &comment)?;
+    let action = ModerationAction::HideComment {
+        comment: comment_id,
+        hidden,
+        previous,
+    };
+    record(community_id, run_id, action)
+}
+
+/// the content is kept in the record so that it could be restored
+pub(crate) fn delete_comment(
+    community_id: CommunityId,
+    run_id: &str,
+    comment_id: ContentId,
+) -> Result<u32, String> {
+    let mut comment = find_comment(comment_id)?;
+    let content = std::mem::replace(&mut comment.content, crate::compress("")?);
+    let hidden = comment.hidden;
+    comment.hidden = true;
+    update_comment(comment_id, &comment)?;
+    let action = ModerationAction::DeleteComment {
+        comment: comment_id,
+        content,
+        hidden,
+    };
+    record(community_id, run_id, action)
+}
+
+pub(crate) fn lock_thread(
+    community_id: CommunityId,
+    run_id: &str,
+    thread_id: ContentId,
+    locked: bool,
+) -> Result<u32, String> {
+    let mut thread = find_thread(thread_id)?;
+    let previous = thread.locked;
+    thread.locked = locked;
+    update_thread(thread_id, &thread)?;
+    let action = ModerationAction::LockThread {
+        thread: thread_id,
+        locked,
+        previous,
+    };
+    record(community_id, run_id, action)
+}
+
+pub(crate) fn pin_thread(
+    community_id: CommunityId,
+    run_id: &str,
+    thread_id: ContentId,
+    pinned: bool,
+) -> Result<u32, String> {
+    let mut thread = find_thread(thread_id)?;
+    let previous = thread.pinned;
+    thread.pinned = pinned;
+    update_thread(thread_id, &thread)?;
+    let action = ModerationAction::PinThread {
+        thread: thread_id,
+        pinned,
+        previous,
+    };
+    record(community_id, run_id, action)
+}
+
+pub(crate) fn mute_user(
+    community: &Community,
+    run_id: &str,
+    account_id: AccountId,
+    minutes: u64,
+) -> Result<u32, String> {
+    (!crate::is_moderator(community, account_id) && account_id != community.agent_pubkey)
+        .then(|| ())
+        .ok_or("The moderators couldn't be muted".to_string())?;
+    (minutes > 0 && minutes <= MAX_MUTE_MINUTES)
+        .then(|| ())
+        .ok_or(format!("The minutes should be in 1..={}", MAX_MUTE_MINUTES))?;
+    let community_id = community.id();
+    let previous = get_mute(community_id, account_id)?;
+    let until = timer::now() + minutes * 60;
+    set_mute(community_id, account_id, Some(until))?;
+    let action = ModerationAction::MuteUser {
+        account: account_id,
+        until,
+        previous,
+    };
+    record(community_id, run_id, action)
+}
+
+fn normalize_tags<'a>(tags: impl Iterator<Item = &'a str>) -> Result<Vec<String>, String> {
+    let mut seen = std::collections::BTreeSet::new();
+    let tags = tags
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty() && seen.insert(t.clone()))
+        .collect::<Vec<_>>();
+    (tags.len() <= MAX_TAGS && tags.iter().all(|t| t.chars().count() <= MAX_TAG_LEN))
+        .then(|| ())
+        .ok_or(format!(
+            "No more than {} tags with {} characters",
+            MAX_TAGS, MAX_TAG_LEN
+        ))?;
+    Ok(tags)
+}
+
+/// replace the tags and return the previous ones
+pub(crate) fn set_tags(thread_id: ContentId, tags: &[String]) -> Result<Vec<String>, String> {
+    let tags = normalize_tags(tags.iter().map(|t| t.as_str()))?;
+    let mut thread = find_thread(thread_id)?;
+    let previous = std::mem::replace(&mut thread.tags, tags);
+    update_thread(thread_id, &thread)?;
+    Ok(previous)
+}
+
+/// the tags are split by commas
+pub(crate) fn tag_thread(
+    community_id: CommunityId,
+    run_id: &str,
+    thread_id: ContentId,
+    tags: &str,
+) -> Result<u32, String> {
+    let tags = normalize_tags(tags.split(','))?;
+    let previous = set_tags(thread_id, &tags)?;
+    let action = ModerationAction::TagThread {
+        thread: thread_id,
+        tags,
+        previous,
+    };
+    record(community_id, run_id, action)
+}
+
+/// whether the actions change the same state
+fn overlaps(a: &ModerationAction, b: &ModerationAction) -> bool {
+    use ModerationAction::*;
+    match (a, b) {
+        (
+            HideComment { comment: x, .. } | DeleteComment { comment: x, .. },
+            HideComment { comment: y, .. } | DeleteComment { comment: y, .. },
+        ) => x == y,
+        (LockThread { thread: x, .. }, LockThread { thread: y, .. })
+        | (PinThread { thread: x, .. }, PinThread { thread: y, .. })
+        | (TagThread { thread: x, .. }, TagThread { thread: y, .. }) => x == y,
+        (MuteUser { account: x, .. }, MuteUser { account: y, .. }) => x == y,
+        _ => false,
+    }
+}
+
+/// the newer unreverted action on the same state
+fn find_superseding(
+    community_id: CommunityId,
+    record: &ModerationRecord,
+) -> Result<Option<u32>, String> {
+    let mut from = record.id.checked_add(1);
+    while let Some(start) = from {
+        let start_key = trie::to_moderation_key(community_id, start);
+        let result = storage::get_range(&start_key, storage::Direction::Forward, 100)
+            .map_err(|e| e.to_string())?;
+        from = None;
+        for (k, v) in result.into_iter() {
+            if k.len() != 16 || !k.starts_with(&start_key[..12]) {
+                return Ok(None);
+            }
+            let newer = ModerationRecord::decode(&mut &v[..]).map_err(|e| e.to_string())?;
+            if !newer.reverted && overlaps(&record.action, &newer.action) {
+                return Ok(Some(newer.id));
+            }
+            from = newer.id.checked_add(1);
+        }
+    }
+    Ok(None)
+}
+
+/// restore the states before the action, the newer actions on the same state are reverted first
+pub(crate) fn revert(community_id: CommunityId, id: u32) -> Result<(), String> {
+    let key = trie::to_moderation_key(community_id, id);
+    let mut record =
+        crate::find::<ModerationRecord>(&key)?.ok_or("Moderation record not found".to_string())?;
+    (!record.reverted)
+        .then(|| ())
+        .ok_or("The action is already reverted".to_string())?;
+    if let Some(newer) = find_superseding(community_id, &record)? {
+        return Err(format!(
+            "The action is superseded by #{}, revert it first",
+            newer
+        ));
+    }
+    match record.action.clone() {
+        ModerationAction::HideComment {
+            comment, previous, ..
+        } => {
+            let mut c = find_comment(comment)?;
+            c.hidden = previous;
+            update_comment(comment, &c)?;
+        }
+        ModerationAction::DeleteComment {
+            comment,
+            content,
+            hidden,
+        } => {
+            let mut c = find_comment(comment)?;
+            c.content = content;
+            c.hidden = hidden;
+            update_comment(comment, &c)?;
+        }
+        ModerationAction::LockThread {
+            thread, previous, ..
+        } => {
+            let mut t = find_thread(thread)?;
+            t.locked = previous;
+            update_thread(thread, &t)?;
+        }
+        ModerationAction::PinThread {
+            thread, previous, ..
+        } => {
+            let mut t = find_thread(thread)?;
+            t.pinned = previous;
+            update_thread(thread, &t)?;
+        }
+        ModerationAction::MuteUser {
+            account, previous, ..
+        } => {
+            set_mute(community_id, account, previous)?;
+        }
+        ModerationAction::TagThread {
+            thread, previous, ..
+        } => {
+            let mut t = find_thread(thread)?;
+            t.tags = previous;
+            update_thread(thread, &t)?;
+        }
+    }
+    record.reverted = true;
+    crate::save(&key, &record)
+}
+
+/// the pinned threads of the community from the oldest
+pub(crate) fn pinned_threads(
+    community_id: CommunityId,
+    limit: usize,
+) -> Result<Vec<Thread>, String> {
+    let start = trie::to_pinned_key(community_id, 0);
+    let result = storage::get_range(&start, storage::Direction::Forward, limit)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, _) in result.into_iter() {
+        if k.len() != 28 || !k.starts_with(&start[..12]) {
+            break;
+        }
+        let thread_id = ContentId::from_be_bytes(k[12..].try_into().expect("qed"));
+        if let Some(thread) = crate::find::<Thread>(&trie::to_content_key(thread_id))? {
+            if thread.pinned {
+                r.push(thread);
+            }
+        }
+    }
+    Ok(r)
+}
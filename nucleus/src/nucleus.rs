@@ -13,16 +13,179 @@ pub mod api {
 
     type SignedArgs<T> = Args<T, EcdsaSignature>;
 
+    fn ensure_admin<T: Encode>(args: &SignedArgs<T>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        crate::is_admin(args.signer)
+            .then(|| ())
+            .ok_or("Only the platform admins are allowed".to_string())
+    }
+
+    #[post(hidden)]
+    pub fn set_llm_key(args: SignedArgs<SetLlmKeyArg>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        let SetLlmKeyArg { llm_name, key } = args.payload;
+        let vendor = match llm_name.as_ref() {
+            "OpenAI" => crate::agent::OPENAI,
+            "DeepSeek" => crate::agent::DEEPSEEK,
+            _ => return Err("unsupported LLM vendor".to_string()),
+        };
+        crate::agent::set_sys_key(vendor, key).map_err(|e| e.to_string())
+    }
+
+    #[post(hidden)]
+    pub fn add_admin(args: SignedArgs<AdminArg>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        crate::save(&trie::to_admin_key(args.payload.account), &1u32)
+    }
+
+    #[post(hidden)]
+    pub fn backfill_feed(args: SignedArgs<BackfillFeedArg>) -> Result<Option<ContentId>, String> {
+        ensure_admin(&args)?;
+        let BackfillFeedArg { from, limit } = args.payload;
+        (limit > 0 && limit as usize <= crate::feed::MAX_BACKFILL_BATCH)
+            .then(|| ())
+            .ok_or(format!(
+                "The limit should be between 1 and {}",
+                crate::feed::MAX_BACKFILL_BATCH
+            ))?;
+        crate::feed::backfill(from, limit as usize)
+    }
+
+    #[post(hidden)]
+    pub fn remove_admin(args: SignedArgs<AdminArg>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        let admins = crate::get_admins()?;
+        (admins.len() > 1 || !admins.contains(&args.payload.account))
+            .then(|| ())
+            .ok_or("The last admin can't be removed".to_string())?;
+        storage::del(&trie::to_admin_key(args.payload.account)).map_err(|e| e.to_string())
+    }
+
+    #[post(hidden)]
+    pub fn set_platform_config(args: SignedArgs<PlatformConfig>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        args.payload.validate()?;
+        crate::save(&trie::PLATFORM_CONFIG_KEY.to_be_bytes(), &args.payload)
+    }
+
+    #[post(hidden)]
+    pub fn set_models(args: SignedArgs<SetModelsArg>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        args.payload
+            .models
+            .iter()
+            .all(|m| ["OpenAI", "DeepSeek"].contains(&m.vendor.as_str()) && !m.model.is_empty())
+            .then(|| ())
+            .ok_or("unsupported LLM vendor or empty model".to_string())?;
+        crate::save(&trie::MODELS_KEY.to_be_bytes(), &args.payload.models)
+    }
+
+    #[get]
+    pub fn get_models() -> Result<Vec<ModelPrice>, String> {
+        crate::get_models()
+    }
+
+    #[post(hidden)]
+    pub fn set_platform_budget(args: SignedArgs<SetAgentBudgetArg>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        let SetAgentBudgetArg { community, budget } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        community.platform_budget = budget;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[get]
+    pub fn get_admins() -> Result<Vec<AccountId>, String> {
+        crate::get_admins()
+    }
+
+    #[post(hidden)]
+    pub fn freeze_community(args: SignedArgs<FreezeCommunityArg>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        let FreezeCommunityArg {
+            community,
+            until,
+            reason,
+        } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        matches!(
+            community.status,
+            CommunityStatus::Active | CommunityStatus::Frozen(..)
+        )
+        .then(|| ())
+        .ok_or("Only active communities could be frozen".to_string())?;
+        let now = timer::now();
+        (until > now)
+            .then(|| ())
+            .ok_or("The freezing time should be in the future".to_string())?;
+        (until - now <= crate::MAX_TIMER_DELAY)
+            .then(|| ())
+            .ok_or(format!(
+                "The community could be frozen for no more than {} seconds",
+                crate::MAX_TIMER_DELAY
+            ))?;
+        set_timer!(
+            Duration::from_secs(until - now),
+            unfreeze_expired,
+            community_id
+        )
+        .map_err(|e| e.to_string())?;
+        community.status = CommunityStatus::Frozen(until, reason);
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
     #[post(hidden)]
-    pub fn set_llm_key(key: String) -> Result<(), String> {
-        crate::agent::set_sys_key(crate::agent::OPENAI, key).map_err(|e| e.to_string())
+    pub fn unfreeze_community(args: SignedArgs<UnfreezeCommunityArg>) -> Result<(), String> {
+        ensure_admin(&args)?;
+        let community_id = crate::name_to_community_id(&args.payload.community)
+            .ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        matches!(community.status, CommunityStatus::Frozen(..))
+            .then(|| ())
+            .ok_or("The community is not frozen".to_string())?;
+        community.status = CommunityStatus::Active;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[timer]
+    pub fn unfreeze_expired(community_id: CommunityId) {
+        let mut community = match crate::try_find_community(community_id) {
+            Ok(community) => community,
+            Err(_) => return,
+        };
+        // the community may be unfrozen manually or frozen again with a later time
+        match community.status {
+            CommunityStatus::Frozen(until, _) if until <= timer::now() => {
+                community.status = CommunityStatus::Active;
+                let key = trie::to_community_key(community_id);
+                if crate::save(&key, &community).is_ok() {
+                    let _ = crate::save_event(Event::CommunityUpdated(community_id));
+                }
+            }
+            _ => {}
+        }
     }
 
     #[post]
     pub fn create_community(args: SignedArgs<CreateCommunityArg>) -> Result<CommunityId, String> {
         let nonce = crate::get_nonce(args.signer)?;
         args.ensure_signed(nonce)?;
-        crate::incr_nonce(args.signer, None)?;
+        crate::incr_nonce(args.signer)?;
         let Args {
             signature: _signature,
             signer,
@@ -51,6 +214,7 @@ pub mod api {
             llm_name,
             llm_api_host,
             llm_key,
+            generation,
         } = payload;
         let token_contract = match token.contract.as_ref() {
             Some(s) => AccountId::from_str(s.trim()).map_err(|e| e.to_string())?,
@@ -73,6 +237,7 @@ pub mod api {
             .map_err(|e| e.to_string())?;
         let pubkey: [u8; 33] = pubkey.try_into().map_err(|_| "TSS key error".to_string())?;
         let llm_vendor = crate::from_llm_settings(llm_name, llm_api_host, llm_key)?;
+        crate::ensure_model_allowed(&llm_vendor, &generation)?;
         let community = Community {
             id: hex::encode(id.encode()),
             name: name.clone(),
@@ -89,8 +254,20 @@ pub mod api {
             llm_vendor,
             llm_assistant_id: Default::default(),
             agent_pubkey: H160::from_compressed(&pubkey)?,
-            status: CommunityStatus::WaitingTx(crate::MIN_ACTIVATE_FEE),
+            status: CommunityStatus::WaitingTx(crate::get_platform_config()?.activate_fee),
             created_time: timer::now() as i64,
+            min_post_reputation: None,
+            pending_owner: None,
+            rate_limit: Default::default(),
+            content_rules: None,
+            image_hosts: vec![],
+            generation,
+            agent_tools: crate::agent::tools::default_tools(),
+            agent_budget: Default::default(),
+            platform_budget: Default::default(),
+            transfer_policy: Default::default(),
+            summary_price: None,
+            min_tag_reputation: None,
         };
         crate::save(&key, &community)?;
         crate::save_event(Event::CommunityCreated(id))?;
@@ -111,11 +288,46 @@ pub mod api {
         Ok(())
     }
 
+    #[post]
+    pub fn retry_activation(args: SignedArgs<RetryActivationArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let community_id = crate::name_to_community_id(&args.payload.community)
+            .ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can retry the activation".to_string())?;
+        crate::agent::retry_activation(&community)
+    }
+
+    #[post]
+    pub fn refund_activation(args: SignedArgs<RefundActivationArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let community_id = crate::name_to_community_id(&args.payload.community)
+            .ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can refund the activation fee".to_string())?;
+        crate::agent::refund_activation(&community)
+    }
+
+    #[get]
+    pub fn get_activation_payment(
+        community_id: CommunityId,
+    ) -> Result<Option<ActivationPayment>, String> {
+        crate::find::<ActivationPayment>(&trie::to_activation_key(community_id))
+    }
+
     #[post]
     pub fn set_mode(args: SignedArgs<SetModeArg>) -> Result<(), String> {
         let account = crate::get_account_info(args.signer)?;
         args.ensure_signed(account.nonce)?;
-        crate::incr_nonce(args.signer, None)?;
+        crate::incr_nonce(args.signer)?;
         let SetModeArg { community, mode } = args.payload;
         let community_id =
             crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
@@ -134,7 +346,7 @@ pub mod api {
     pub fn set_community(args: SignedArgs<SetCommunityArg>) -> Result<(), String> {
         let account = crate::get_account_info(args.signer)?;
         args.ensure_signed(account.nonce)?;
-        crate::incr_nonce(args.signer, None)?;
+        crate::incr_nonce(args.signer)?;
         let SetCommunityArg {
             community,
             logo,
@@ -158,6 +370,472 @@ pub mod api {
         Ok(())
     }
 
+    #[post]
+    pub fn update_agent(args: SignedArgs<UpdateAgentArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        args.payload.validate()?;
+        let UpdateAgentArg {
+            community,
+            prompt,
+            generation,
+            tools,
+        } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can update the agent".to_string())?;
+        if let Some(prompt) = prompt {
+            crate::save_prompt_revision(&community)?;
+            community.prompt = prompt;
+        }
+        if let Some(generation) = generation {
+            crate::ensure_model_allowed(&community.llm_vendor, &generation)?;
+            community.generation = generation;
+        }
+        if let Some(mut tools) = tools {
+            tools.sort();
+            tools.dedup();
+            tools
+                .iter()
+                .all(|t| crate::agent::tools::find(t).is_some())
+                .then(|| ())
+                .ok_or("Unknown tool".to_string())?;
+            community.agent_tools = tools;
+        }
+        crate::agent::update_agent(&community)?;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn propose_owner(args: SignedArgs<ProposeOwnerArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let ProposeOwnerArg {
+            community,
+            new_owner,
+        } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can transfer the ownership".to_string())?;
+        (new_owner != Some(args.signer))
+            .then(|| ())
+            .ok_or("You are already the owner".to_string())?;
+        community.pending_owner = new_owner;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn accept_ownership(args: SignedArgs<AcceptOwnershipArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let community_id = crate::name_to_community_id(&args.payload.community)
+            .ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.pending_owner == Some(args.signer))
+            .then(|| ())
+            .ok_or("You are not the proposed owner".to_string())?;
+        let prev_owner = community.creator;
+        // the invite tickets were paid by the previous owner for the community, so move them too
+        let prev_tickets_key = to_invitecode_amt_key(community_id, prev_owner);
+        let prev_tickets: u64 = crate::find(prev_tickets_key.as_ref())?.unwrap_or_default();
+        if prev_tickets > 0 {
+            let tickets_key = to_invitecode_amt_key(community_id, args.signer);
+            let tickets: u64 = crate::find(tickets_key.as_ref())?.unwrap_or_default();
+            crate::save(tickets_key.as_ref(), &(tickets + prev_tickets))?;
+            storage::del(prev_tickets_key.as_ref()).map_err(|e| e.to_string())?;
+        }
+        save(&to_permission_key(community_id, args.signer), &1u32)?;
+        // `creator_bnb_benefit` is accounted on the community and always belongs to the current creator
+        community.creator = args.signer;
+        community.pending_owner = None;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn set_min_reputation(args: SignedArgs<SetMinReputationArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let SetMinReputationArg {
+            community,
+            min_post_reputation,
+            min_tag_reputation,
+        } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can set the minimum reputation".to_string())?;
+        community.min_post_reputation = min_post_reputation;
+        community.min_tag_reputation = min_tag_reputation;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn set_rate_limit(args: SignedArgs<SetRateLimitArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let SetRateLimitArg {
+            community,
+            rate_limit,
+        } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can set the rate limit".to_string())?;
+        community.rate_limit = rate_limit;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn set_content_rules(args: SignedArgs<SetContentRulesArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let SetContentRulesArg {
+            community,
+            content_rules,
+        } = args.payload;
+        if let Some(ref rules) = content_rules {
+            rules.validate_within(&ContentRules::default())?;
+        }
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can set the content rules".to_string())?;
+        community.content_rules = content_rules;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn set_image_hosts(args: SignedArgs<SetImageHostsArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        args.payload.validate()?;
+        let SetImageHostsArg { community, hosts } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can set the image hosts".to_string())?;
+        community.image_hosts = hosts.into_iter().map(|h| h.to_lowercase()).collect();
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn set_agent_budget(args: SignedArgs<SetAgentBudgetArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let SetAgentBudgetArg { community, budget } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can set the agent budget".to_string())?;
+        community.agent_budget = budget;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn set_transfer_policy(args: SignedArgs<SetTransferPolicyArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let SetTransferPolicyArg { community, policy } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can set the transfer policy".to_string())?;
+        community.transfer_policy = policy;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn summarize_thread(args: SignedArgs<SummarizeThreadArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let thread_id = args.payload.thread;
+        (!trie::is_comment(thread_id))
+            .then(|| ())
+            .ok_or("Not a thread".to_string())?;
+        let thread = crate::find::<Thread>(&trie::to_content_key(thread_id))?
+            .ok_or("Thread not found".to_string())?;
+        let community = crate::try_find_community(thread.community_id())?;
+        crate::ensure_active(&community)?;
+        crate::agent::summary::request(&community, &thread, args.signer)
+    }
+
+    #[post]
+    pub fn set_summary_price(args: SignedArgs<SetSummaryPriceArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let SetSummaryPriceArg { community, price } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let mut community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can set the summary price".to_string())?;
+        community.summary_price = price;
+        let key = trie::to_community_key(community_id);
+        crate::save(&key, &community)?;
+        crate::save_event(Event::CommunityUpdated(community_id))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn add_agent_job(args: SignedArgs<AddAgentJobArg>) -> Result<u32, String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        args.payload.validate(timer::now())?;
+        let community_id = crate::name_to_community_id(&args.payload.community)
+            .ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can add agent jobs".to_string())?;
+        crate::agent::jobs::add_job(community_id, args.payload)
+    }
+
+    #[post]
+    pub fn remove_agent_job(args: SignedArgs<RemoveAgentJobArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let RemoveAgentJobArg { community, id } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer)
+            .then(|| ())
+            .ok_or("Only the creator can remove agent jobs".to_string())?;
+        crate::agent::jobs::remove_job(community_id, id)
+    }
+
+    #[get]
+    pub fn get_agent_jobs(community_id: CommunityId) -> Result<Vec<AgentJob>, String> {
+        crate::agent::jobs::get_jobs(community_id)
+    }
+
+    #[get]
+    pub fn get_transfer_stats(community_id: CommunityId) -> Result<TransferStats, String> {
+        crate::agent::treasury::get_transfer_stats(community_id)
+    }
+
+    /// the usage of a day since epoch, or the total if `day` is `None`
+    #[get]
+    pub fn get_agent_usage(
+        community_id: CommunityId,
+        day: Option<u64>,
+    ) -> Result<AgentUsage, String> {
+        crate::usage::get_usage(community_id, day)
+    }
+
+    #[post]
+    pub fn revert_moderation(args: SignedArgs<RevertModerationArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let RevertModerationArg { community, id } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        crate::is_moderator(&community, args.signer)
+            .then(|| ())
+            .ok_or("Only moderators can revert the moderation".to_string())?;
+        crate::moderation::revert(community_id, id)
+    }
+
+    #[get]
+    pub fn get_moderation_log(
+        community_id: CommunityId,
+        from: u32,
+        limit: u32,
+    ) -> Result<Vec<ModerationRecord>, String> {
+        crate::moderation::get_log(community_id, from, limit)
+    }
+
+    /// the timestamp until which the account is muted
+    #[get]
+    pub fn get_mute(
+        community_id: CommunityId,
+        account_id: AccountId,
+    ) -> Result<Option<u64>, String> {
+        crate::moderation::get_mute(community_id, account_id)
+    }
+
+    #[get]
+    pub fn get_post_stats(
+        community_id: CommunityId,
+        account_id: AccountId,
+    ) -> Result<PostStats, String> {
+        crate::ratelimit::get_post_stats(community_id, account_id)
+    }
+
+    #[post]
+    pub fn penalize(args: SignedArgs<PenalizeArg>) -> Result<i64, String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let PenalizeArg {
+            community,
+            account,
+            points,
+        } = args.payload;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        crate::is_moderator(&community, args.signer)
+            .then(|| ())
+            .ok_or("Only moderators can penalize users".to_string())?;
+        crate::reputation::contribute(
+            community_id,
+            account,
+            crate::reputation::Contribution::Penalty(points),
+        )
+    }
+
+    #[get]
+    pub fn get_reputation(community_id: CommunityId, account_id: AccountId) -> Result<i64, String> {
+        crate::reputation::get_reputation(community_id, account_id)
+    }
+
+    #[get]
+    pub fn get_top_reputations(
+        community_id: CommunityId,
+        limit: u32,
+    ) -> Result<Vec<(AccountId, i64)>, String> {
+        (limit <= 100)
+            .then(|| ())
+            .ok_or("limit should be no more than 100".to_string())?;
+        crate::reputation::top_reputations(community_id, limit as usize)
+    }
+
+    #[post]
+    pub fn react(args: SignedArgs<ReactArg>) -> Result<u32, String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let ReactArg { content, liked } = args.payload;
+        let community = crate::try_find_community(vemodel::get_belongs_to(content))?;
+        crate::ensure_active(&community)?;
+        crate::reputation::react(&community, content, args.signer, liked)
+    }
+
+    #[get]
+    pub fn get_reactions(content_id: ContentId) -> Result<u32, String> {
+        crate::reputation::get_reactions(content_id)
+    }
+
+    /// only one answer could be accepted by the author of the thread
+    #[post]
+    pub fn accept_answer(args: SignedArgs<AcceptAnswerArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let AcceptAnswerArg {
+            comment: comment_id,
+            bounty,
+        } = args.payload;
+        trie::is_comment(comment_id)
+            .then(|| ())
+            .ok_or("Not a comment".to_string())?;
+        let comment = crate::find::<Comment>(&trie::to_content_key(comment_id))?
+            .ok_or("Comment not found".to_string())?;
+        let thread_id = comment.thread_id();
+        let thread_key = trie::to_content_key(thread_id);
+        let mut thread =
+            crate::find::<Thread>(&thread_key)?.ok_or("Thread not found".to_string())?;
+        (thread.author == args.signer)
+            .then(|| ())
+            .ok_or("Only the author of the thread can accept an answer".to_string())?;
+        (comment.author != args.signer && !comment.hidden)
+            .then(|| ())
+            .ok_or("The comment can't be accepted".to_string())?;
+        thread
+            .accepted_answer
+            .is_none()
+            .then(|| ())
+            .ok_or("An answer was accepted already".to_string())?;
+        let community = crate::try_find_community(thread.community_id())?;
+        crate::ensure_active(&community)?;
+        if bounty > 0 {
+            crate::transfer(community.id(), args.signer, comment.author, bounty)?;
+        }
+        thread.accepted_answer = Some((comment.id.clone(), bounty));
+        crate::save(&thread_key, &thread)?;
+        crate::save_event(Event::ThreadUpdated(thread_id))?;
+        crate::reputation::contribute(
+            community.id(),
+            comment.author,
+            crate::reputation::Contribution::Answer(bounty),
+        )
+        .map(|_| ())
+    }
+
+    #[post]
+    pub fn tag_thread(args: SignedArgs<TagThreadArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let TagThreadArg { thread, tags } = args.payload;
+        let community = crate::try_find_community(vemodel::get_belongs_to(thread))?;
+        crate::ensure_active(&community)?;
+        crate::reputation::ensure_tagging(&community, args.signer)?;
+        crate::moderation::set_tags(thread, &tags).map(|_| ())
+    }
+
     #[post]
     pub fn pay_to_join(arg: PaysFeeArg) -> Result<(), String> {
         let PaysFeeArg { community, tx } = arg;
@@ -176,7 +854,7 @@ pub mod api {
     pub fn invite_user(args: SignedArgs<InviteUserArgs>) -> Result<(), String> {
         let account = crate::get_account_info(args.signer)?;
         args.ensure_signed(account.nonce)?;
-        crate::incr_nonce(args.signer, None)?;
+        crate::incr_nonce(args.signer)?;
         let content = args.payload;
         let community_id = crate::name_to_community_id(&content.community)
             .ok_or("Invalid community name".to_string())?;
@@ -224,32 +902,33 @@ pub mod api {
     #[post]
     pub fn post_thread(args: SignedArgs<PostThreadArg>) -> Result<ContentId, String> {
         let account = crate::get_account_info(args.signer)?;
-        account
-            .allow_post(timer::now())
-            .then(|| ())
-            .ok_or("You're sending messages too frequently.".to_string())?;
         args.ensure_signed(account.nonce)?;
-        crate::incr_nonce(args.signer, Some(timer::now()))?;
+        crate::incr_nonce(args.signer)?;
         let Args {
             signature: _signature,
             signer,
             nonce: _nonce,
             payload,
         } = args;
+        let community_id = crate::name_to_community_id(&payload.community)
+            .ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        let rules = community.content_rules();
+        payload.validate(&rules, &community.image_hosts)?;
         let PostThreadArg {
-            community,
+            community: _,
             title,
             content,
             images,
             mention,
         } = payload;
-        let text = crate::decompress(&content)?;
-        let community_id =
-            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
-        let community = crate::try_find_community(community_id)?;
-        (community.status == CommunityStatus::Active)
-            .then(|| ())
-            .ok_or("The community is inactive.".to_string())?;
+        let title = title.trim().to_string();
+        let text = crate::decompress(&content, rules.max_content_len as usize)?;
+        let scope = (community_id as u128) << 64;
+        let digest = crate::check_duplicate(signer, scope, &rules, &title, &text)?;
+        crate::ensure_active(&community)?;
+        crate::reputation::ensure_reputation(&community, signer)?;
+        crate::moderation::ensure_not_muted(community_id, signer, timer::now())?;
         match community.mode {
             CommunityMode::Public => {
                 let key = trie::to_permission_key(community_id, account.address);
@@ -259,6 +938,12 @@ pub mod api {
                 validate_write_permission(community_id, args.signer)?;
             }
         }
+        let stats = crate::ratelimit::check(
+            &community,
+            signer,
+            crate::ratelimit::Action::Post,
+            timer::now(),
+        )?;
         let id = crate::allocate_thread_id(community_id)?;
         let key = trie::to_content_key(id);
         let thread = Thread {
@@ -266,13 +951,23 @@ pub mod api {
             community_name: community.name.clone(),
             title,
             content,
-            images,
+            legacy_images: vec![],
             author: signer,
             mention,
             llm_session_id: Default::default(),
             created_time: timer::now() as i64,
+            images,
+            locked: false,
+            pinned: false,
+            tags: vec![],
+            summary: None,
+            summarized_at: None,
+            accepted_answer: None,
         };
         crate::save(&key, &thread)?;
+        crate::ratelimit::record(community_id, signer, &stats)?;
+        crate::save(&trie::to_last_post_key(signer, scope), &digest)?;
+        crate::feed::index_thread(&thread)?;
         crate::save_event(Event::ThreadPosted(id))?;
         crate::agent::create_session_and_run(&community, &thread, &text)?;
         Ok(id)
@@ -281,18 +976,18 @@ pub mod api {
     #[post]
     pub fn post_comment(args: SignedArgs<PostCommentArg>) -> Result<ContentId, String> {
         let account = crate::get_account_info(args.signer)?;
-        account
-            .allow_post(timer::now())
-            .then(|| ())
-            .ok_or("You're sending messages too frequently.".to_string())?;
         args.ensure_signed(account.nonce)?;
-        crate::incr_nonce(args.signer, Some(timer::now()))?;
+        crate::incr_nonce(args.signer)?;
         let Args {
             signature: _signature,
             signer,
             nonce: _nonce,
             payload,
         } = args;
+        let community_id = (payload.thread >> 64) as u32;
+        let community = crate::try_find_community(community_id)?;
+        let rules = community.content_rules();
+        payload.validate(&rules, &community.image_hosts)?;
         let PostCommentArg {
             thread: thread_id,
             content,
@@ -300,20 +995,28 @@ pub mod api {
             mention,
             reply_to,
         } = payload;
-        let text = crate::decompress(&content)?;
-        let community_id = (thread_id >> 64) as u32;
-        let community = crate::try_find_community(community_id)?;
-        (community.status == CommunityStatus::Active)
+        let text = crate::decompress(&content, rules.max_content_len as usize)?;
+        let digest = crate::check_duplicate(signer, thread_id, &rules, "", &text)?;
+        crate::ensure_active(&community)?;
+        crate::reputation::ensure_reputation(&community, signer)?;
+        crate::moderation::ensure_not_muted(community_id, signer, timer::now())?;
+        let thread_key = trie::to_content_key(thread_id);
+        let thread = crate::find::<Thread>(&thread_key)?.ok_or("Thread not found".to_string())?;
+        (!thread.locked || crate::is_moderator(&community, signer))
             .then(|| ())
-            .ok_or("The community is inactive.".to_string())?;
+            .ok_or("The thread is locked".to_string())?;
         match community.mode {
             CommunityMode::Public => {}
             _ => {
                 validate_write_permission(community_id, args.signer)?;
             }
         }
-        let thread_key = trie::to_content_key(thread_id);
-        let thread = crate::find::<Thread>(&thread_key)?.ok_or("Thread not found".to_string())?;
+        let stats = crate::ratelimit::check(
+            &community,
+            signer,
+            crate::ratelimit::Action::Comment,
+            timer::now(),
+        )?;
         let id = crate::allocate_comment_id(thread_id)?;
         let key = trie::to_content_key(id);
         let reply_to = reply_to
@@ -323,13 +1026,17 @@ pub mod api {
         let comment = Comment {
             id: hex::encode(id.encode()),
             content,
-            images,
+            legacy_images: vec![],
             author: signer,
             mention,
             reply_to,
             created_time: timer::now() as i64,
+            images,
+            hidden: false,
         };
         crate::save(&key, &comment)?;
+        crate::ratelimit::record(community_id, signer, &stats)?;
+        crate::save(&trie::to_last_post_key(signer, thread_id), &digest)?;
         crate::save_event(Event::CommentPosted(id))?;
         if mention_agent {
             crate::agent::append_message_then_run(&community, &thread, &comment, &text)?;
@@ -399,7 +1106,7 @@ pub mod api {
     pub fn set_alias(args: SignedArgs<SetAliasArg>) -> Result<(), String> {
         let nonce = crate::get_nonce(args.signer)?;
         args.ensure_signed(nonce)?;
-        crate::incr_nonce(args.signer, None)?;
+        crate::incr_nonce(args.signer)?;
         args.payload.validate()?;
         let alias = crate::into_account_id(&args.payload.alias);
         let alias_key = trie::to_account_key(alias);
@@ -417,9 +1124,191 @@ pub mod api {
         let account_key = trie::to_account_key(args.signer);
         crate::save(&account_key, &AccountData::Pubkey(account))?;
         crate::save(&alias_key, &AccountData::AliasOf(args.signer))?;
+        crate::save_event(Event::AccountUpdated(args.signer))?;
+        Ok(())
+    }
+
+    #[post]
+    pub fn set_profile(args: SignedArgs<SetProfileArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        args.payload.validate()?;
+        let SetProfileArg {
+            avatar,
+            bio,
+            links,
+            language,
+        } = args.payload;
+        let mut account = crate::get_account_info(args.signer)?;
+        account.avatar = avatar;
+        account.bio = bio;
+        account.links = links;
+        account.language = language;
+        let account_key = trie::to_account_key(args.signer);
+        crate::save(&account_key, &AccountData::Pubkey(account))?;
+        crate::save_event(Event::AccountUpdated(args.signer))?;
         Ok(())
     }
 
+    #[post]
+    pub fn follow(args: SignedArgs<FollowArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let FollowArg { followee } = args.payload;
+        match followee {
+            Followee::Community(community_id) => {
+                crate::try_find_community(community_id)?;
+            }
+            Followee::Account(account_id) => {
+                (account_id != args.signer)
+                    .then(|| ())
+                    .ok_or("You can't follow yourself".to_string())?;
+            }
+        }
+        crate::feed::follow(args.signer, followee, timer::now())
+    }
+
+    #[post]
+    pub fn unfollow(args: SignedArgs<FollowArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        crate::feed::unfollow(args.signer, args.payload.followee)
+    }
+
+    #[get]
+    pub fn get_following(
+        account_id: AccountId,
+        gt: Option<Followee>,
+        limit: u32,
+    ) -> Result<Vec<Followee>, String> {
+        (limit <= 100)
+            .then(|| ())
+            .ok_or("limit should be no more than 100".to_string())?;
+        crate::feed::list_following(account_id, gt, limit as usize)
+    }
+
+    #[get]
+    pub fn get_followers(
+        followee: Followee,
+        gt: Option<AccountId>,
+        limit: u32,
+    ) -> Result<Vec<AccountId>, String> {
+        (limit <= 100)
+            .then(|| ())
+            .ok_or("limit should be no more than 100".to_string())?;
+        crate::feed::list_followers(followee, gt, limit as usize)
+    }
+
+    #[get]
+    pub fn get_feed(
+        account_id: AccountId,
+        cursor: Option<FeedCursor>,
+    ) -> Result<Vec<Thread>, String> {
+        crate::feed::get_feed(account_id, cursor)
+    }
+
+    #[post]
+    pub fn register_pubkey(args: SignedArgs<RegisterPubkeyArg>) -> Result<(), String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        let pubkey = args.recover_signer(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        crate::message::save_pubkey(args.signer, pubkey)
+    }
+
+    #[get]
+    pub fn get_pubkey(account_id: AccountId) -> Result<Option<Vec<u8>>, String> {
+        crate::message::get_pubkey(account_id)
+    }
+
+    #[post]
+    pub fn send_message(args: SignedArgs<SendMessageArg>) -> Result<u64, String> {
+        let nonce = crate::get_nonce(args.signer)?;
+        args.ensure_signed(nonce)?;
+        crate::incr_nonce(args.signer)?;
+        let Args {
+            signature: _signature,
+            signer,
+            nonce: _nonce,
+            payload,
+        } = args;
+        payload.validate()?;
+        let SendMessageArg {
+            recipient,
+            ciphertext,
+            sender_ciphertext,
+        } = payload;
+        (recipient != signer)
+            .then(|| ())
+            .ok_or("You can't send messages to yourself".to_string())?;
+        crate::message::get_pubkey(recipient)?
+            .ok_or("The recipient hasn't registered a public key".to_string())?;
+        crate::message::send(
+            signer,
+            recipient,
+            ciphertext,
+            sender_ciphertext,
+            timer::now() as i64,
+        )
+    }
+
+    /// only the participants could read a conversation
+    #[get]
+    pub fn get_conversation(
+        args: SignedArgs<GetConversationArg>,
+    ) -> Result<Vec<DirectMessage>, String> {
+        crate::message::ensure_query_signed(&args, args.payload.expire_at)?;
+        let GetConversationArg {
+            peer,
+            before,
+            limit,
+            expire_at: _,
+        } = args.payload;
+        (limit <= 100)
+            .then(|| ())
+            .ok_or("limit should be no more than 100".to_string())?;
+        let conversation = conversation_id(args.signer, peer);
+        crate::message::get_conversation(conversation, before, limit as usize)
+    }
+
+    #[get]
+    pub fn get_prompt_history(
+        args: SignedArgs<GetPromptHistoryArg>,
+    ) -> Result<Vec<(u32, PromptRevision)>, String> {
+        crate::message::ensure_query_signed(&args, args.payload.expire_at)?;
+        let GetPromptHistoryArg {
+            community,
+            before,
+            limit,
+            expire_at: _,
+        } = args.payload;
+        (limit <= 100)
+            .then(|| ())
+            .ok_or("limit should be no more than 100".to_string())?;
+        let community_id =
+            crate::name_to_community_id(&community).ok_or("Invalid community name".to_string())?;
+        let community = crate::try_find_community(community_id)?;
+        (community.creator == args.signer || crate::is_admin(args.signer))
+            .then(|| ())
+            .ok_or("Only the creator or the admins can read the prompt history".to_string())?;
+        crate::get_prompt_history(community_id, before, limit as usize)
+    }
+
+    /// the peers of an account are only listed to itself
+    #[get]
+    pub fn list_conversations(
+        args: SignedArgs<ListConversationsArg>,
+    ) -> Result<Vec<Conversation>, String> {
+        crate::message::ensure_query_signed(&args, args.payload.expire_at)?;
+        let ListConversationsArg { gt, limit, .. } = args.payload;
+        (limit <= 100)
+            .then(|| ())
+            .ok_or("limit should be no more than 100".to_string())?;
+        crate::message::list_conversations(args.signer, gt, limit as usize)
+    }
+
     #[get]
     pub fn get_account_info(account_id: AccountId) -> Result<Account, String> {
         crate::get_account_info(account_id)
@@ -476,9 +1365,25 @@ pub mod api {
 
     #[init]
     pub fn init() {
+        let admins = crate::seed_admins().expect("save admin failed");
+        // the platform couldn't be managed without any admin
+        assert!(admins > 0, "no valid admin in VEFORUM_ADMINS");
         set_timer!(Duration::from_secs(5), query_bsc_gas_price).expect("set timer failed");
     }
 
+    /// record the genesis admins for the nucleus upgraded from a version without admins,
+    /// it takes effect only once since nothing is done if any admin exists
+    #[post(hidden)]
+    pub fn bootstrap_admins() -> Result<(), String> {
+        crate::get_admins()?
+            .is_empty()
+            .then(|| ())
+            .ok_or("The admins are already set".to_string())?;
+        (crate::seed_admins()? > 0)
+            .then(|| ())
+            .ok_or("No valid admin in VEFORUM_ADMINS".to_string())
+    }
+
     #[timer]
     pub fn query_bsc_gas_price() {
         vrs_core_sdk::println!("start to query bas gasprice");
@@ -493,10 +1398,15 @@ pub mod api {
         validate_write_permission(community_id, user).is_ok()
     }
 
+    /// returns a `Result` unlike the fixed fee before, so a broken config isn't read as free
+    #[get]
+    pub fn get_invite_fee() -> Result<u128, String> {
+        crate::get_platform_config().map(|c| c.invite_fee)
+    }
+
     #[get]
-    pub fn get_invite_fee() -> u128 {
-        use crate::MIN_INVITE_FEE;
-        MIN_INVITE_FEE
+    pub fn get_platform_config() -> Result<PlatformConfig, String> {
+        crate::get_platform_config()
     }
 
     #[get]
@@ -0,0 +1,102 @@
+use crate::trie;
+use vemodel::*;
+
+const SECONDS_PER_DAY: u64 = 86400;
+const DAILY_SUMMARY_REQUESTS: u32 = 10;
+
+pub(crate) enum Action {
+    Post,
+    Comment,
+}
+
+pub(crate) fn get_post_stats(
+    community_id: CommunityId,
+    account_id: AccountId,
+) -> Result<PostStats, String> {
+    let key = trie::to_post_stats_key(community_id, account_id);
+    crate::find::<PostStats>(&key).map(|v| v.unwrap_or_default())
+}
+
+/// check the cooldown and the daily quota of the community, return the stats to be recorded
+/// by `record` once the post is stored
+pub(crate) fn check(
+    community: &Community,
+    account_id: AccountId,
+    action: Action,
+    now: u64,
+) -> Result<PostStats, String> {
+    let rate_limit = &community.rate_limit;
+    let mut stats = get_post_stats(community.id(), account_id)?;
+    let exempted = rate_limit.exempt_moderators && crate::is_moderator(community, account_id);
+    if !exempted {
+        let cooldown = match rate_limit.cooldown {
+            Some(cooldown) => cooldown,
+            None => crate::get_platform_config()?.post_cooling_down,
+        };
+        (stats.last_post_at + cooldown < now)
+            .then(|| ())
+            .ok_or("You're sending messages too frequently.".to_string())?;
+    }
+    let day = now / SECONDS_PER_DAY;
+    if stats.day != day {
+        stats.day = day;
+        stats.posts = 0;
+        stats.comments = 0;
+    }
+    match action {
+        Action::Post => stats.total_threads += 1,
+        Action::Comment => stats.total_comments += 1,
+    }
+    let (count, quota, kind) = match action {
+        Action::Post => (&mut stats.posts, rate_limit.daily_posts, "threads"),
+        Action::Comment => (&mut stats.comments, rate_limit.daily_comments, "comments"),
+    };
+    (exempted || quota == 0 || *count < quota)
+        .then(|| ())
+        .ok_or(format!(
+            "You can post no more than {} {} per day in this community",
+            quota, kind
+        ))?;
+    *count += 1;
+    stats.last_post_at = now;
+    Ok(stats)
+}
+
+pub(crate) fn record(
+    community_id: CommunityId,
+    account_id: AccountId,
+    stats: &PostStats,
+) -> Result<(), String> {
+    crate::save(&trie::to_post_stats_key(community_id, account_id), stats)
+}
+
+/// check the summaries requested by an account in the community today, return the day and the
+/// count to be recorded by `record_summary` once requested
+pub(crate) fn check_summary(
+    community_id: CommunityId,
+    account_id: AccountId,
+    now: u64,
+) -> Result<(u64, u32), String> {
+    let day = now / SECONDS_PER_DAY;
+    let key = trie::to_summary_quota_key(community_id, account_id);
+    let count = match crate::find::<(u64, u32)>(&key)? {
+        Some((last, count)) if last == day => count,
+        _ => 0,
+    };
+    (count < DAILY_SUMMARY_REQUESTS).then(|| ()).ok_or(format!(
+        "You can request no more than {} summaries per day in this community",
+        DAILY_SUMMARY_REQUESTS
+    ))?;
+    Ok((day, count + 1))
+}
+
+pub(crate) fn record_summary(
+    community_id: CommunityId,
+    account_id: AccountId,
+    quota: (u64, u32),
+) -> Result<(), String> {
+    crate::save(
+        &trie::to_summary_quota_key(community_id, account_id),
+        &quota,
+    )
+}
@@ -0,0 +1,154 @@
+use crate::trie;
+use vemodel::*;
+use vrs_core_sdk::{codec::Decode, storage};
+
+const ACCEPTED_ANSWER_POINTS: i64 = 10;
+
+/// points of a contribution, rewards and bounties are counted by the whole tokens
+pub(crate) enum Contribution {
+    Reward(u128),
+    Penalty(u64),
+    /// a like received or taken back
+    Reaction(bool),
+    /// an answer accepted with the bounty
+    Answer(u128),
+}
+
+impl Contribution {
+    fn points(&self) -> i64 {
+        match self {
+            Self::Reward(amount) => (*amount).min(i64::MAX as u128) as i64,
+            Self::Penalty(points) => -((*points).min(i64::MAX as u64) as i64),
+            Self::Reaction(liked) => {
+                if *liked {
+                    1
+                } else {
+                    -1
+                }
+            }
+            Self::Answer(bounty) => {
+                ACCEPTED_ANSWER_POINTS.saturating_add((*bounty).min(i64::MAX as u128) as i64)
+            }
+        }
+    }
+}
+
+pub(crate) fn get_reputation(
+    community_id: CommunityId,
+    account_id: AccountId,
+) -> Result<i64, String> {
+    let key = trie::to_reputation_key(community_id, account_id);
+    crate::find::<i64>(&key).map(|v| v.unwrap_or_default())
+}
+
+/// update the score and its rank index incrementally
+pub(crate) fn contribute(
+    community_id: CommunityId,
+    account_id: AccountId,
+    contribution: Contribution,
+) -> Result<i64, String> {
+    let prev = get_reputation(community_id, account_id)?;
+    let score = prev.saturating_add(contribution.points());
+    storage::del(&trie::to_reputation_rank_key(
+        community_id,
+        prev,
+        account_id,
+    ))
+    .map_err(|e| e.to_string())?;
+    crate::save(&trie::to_reputation_key(community_id, account_id), &score)?;
+    crate::save(
+        &trie::to_reputation_rank_key(community_id, score, account_id),
+        &score,
+    )?;
+    Ok(score)
+}
+
+pub(crate) fn top_reputations(
+    community_id: CommunityId,
+    limit: usize,
+) -> Result<Vec<(AccountId, i64)>, String> {
+    let start = trie::to_reputation_rank_key(community_id, i64::MAX, H160([0xff; 20]));
+    let result = storage::get_range(&start, storage::Direction::Reverse, limit)
+        .map_err(|e| e.to_string())?;
+    let mut r = vec![];
+    for (k, v) in result.into_iter() {
+        if k.len() != 40 || !k.starts_with(&start[..12]) {
+            continue;
+        }
+        let account_id = AccountId::from_slice(&k[20..])?;
+        let score = i64::decode(&mut &v[..]).map_err(|e| e.to_string())?;
+        r.push((account_id, score));
+    }
+    Ok(r)
+}
+
+pub(crate) fn ensure_reputation(
+    community: &Community,
+    account_id: AccountId,
+) -> Result<(), String> {
+    match community.min_post_reputation {
+        Some(min) if !crate::is_moderator(community, account_id) => {
+            (get_reputation(community.id(), account_id)? >= min)
+                .then(|| ())
+                .ok_or(format!(
+                    "At least {} reputation is required to post in this community",
+                    min
+                ))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// the moderators could always tag threads, the others need `min_tag_reputation`
+pub(crate) fn ensure_tagging(community: &Community, account_id: AccountId) -> Result<(), String> {
+    if crate::is_moderator(community, account_id) {
+        return Ok(());
+    }
+    match community.min_tag_reputation {
+        Some(min) => (get_reputation(community.id(), account_id)? >= min)
+            .then(|| ())
+            .ok_or(format!(
+                "At least {} reputation is required to tag threads",
+                min
+            )),
+        None => Err("Only the moderators can tag threads".to_string()),
+    }
+}
+
+pub(crate) fn get_reactions(content_id: ContentId) -> Result<u32, String> {
+    crate::find::<u32>(&trie::to_reaction_count_key(content_id)).map(|v| v.unwrap_or_default())
+}
+
+/// like a content or take the like back, the author gains or loses a point, return the likes
+pub(crate) fn react(
+    community: &Community,
+    content_id: ContentId,
+    account_id: AccountId,
+    liked: bool,
+) -> Result<u32, String> {
+    let key = trie::to_content_key(content_id);
+    let author = if trie::is_comment(content_id) {
+        crate::find::<Comment>(&key)?.map(|c| c.author)
+    } else {
+        crate::find::<Thread>(&key)?.map(|t| t.author)
+    }
+    .ok_or("Content not found".to_string())?;
+    (author != account_id)
+        .then(|| ())
+        .ok_or("You can't like your own post".to_string())?;
+    let reaction_key = trie::to_reaction_key(content_id, account_id);
+    let count = get_reactions(content_id)?;
+    if crate::find::<u32>(&reaction_key)?.is_some() == liked {
+        return Ok(count);
+    }
+    let count = if liked {
+        crate::save(&reaction_key, &1u32)?;
+        count + 1
+    } else {
+        storage::del(&reaction_key).map_err(|e| e.to_string())?;
+        count.saturating_sub(1)
+    };
+    crate::save(&trie::to_reaction_count_key(content_id), &count)?;
+    contribute(community.id(), author, Contribution::Reaction(liked))?;
+    Ok(count)
+}
@@ -19,7 +19,36 @@ pub const REWARD_SEQ_PREFIX: u64 = 0x00000007_00000000;
 pub const INVITE_AMT_PREFIX: u64 = 0x00000008_00000000;
 pub const GASPRICE_STORAGE_KEY: u64 = 0x00000009_00000000;
 pub const BALANCE_KEY_PREFIX: u64 = 0x0000000a_00000000;
+pub const FOLLOWING_KEY_PREFIX: u64 = 0x0000000b_00000000;
+pub const FOLLOWER_KEY_PREFIX: u64 = 0x0000000c_00000000;
+pub const AUTHOR_THREAD_PREFIX: u64 = 0x0000000d_00000000;
+pub const COMMUNITY_THREAD_PREFIX: u64 = 0x0000000e_00000000;
 pub const KEY_STORE: u64 = 0x00000010_00000000;
+pub const PUBKEY_KEY_PREFIX: u64 = 0x00000011_00000000;
+pub const MESSAGE_KEY_PREFIX: u64 = 0x00000012_00000000;
+pub const CONVERSATION_KEY_PREFIX: u64 = 0x00000013_00000000;
+pub const REPUTATION_KEY_PREFIX: u64 = 0x00000014_00000000;
+pub const REPUTATION_RANK_PREFIX: u64 = 0x00000015_00000000;
+pub const ACTIVATION_KEY_PREFIX: u64 = 0x00000016_00000000;
+pub const ADMIN_KEY_PREFIX: u64 = 0x00000017_00000000;
+pub const PLATFORM_CONFIG_KEY: u64 = 0x00000018_00000000;
+pub const POST_STATS_KEY_PREFIX: u64 = 0x00000019_00000000;
+pub const LAST_POST_KEY_PREFIX: u64 = 0x0000001a_00000000;
+pub const CHAT_SESSION_KEY_PREFIX: u64 = 0x0000001b_00000000;
+pub const MODELS_KEY: u64 = 0x0000001c_00000000;
+pub const PROMPT_HISTORY_PREFIX: u64 = 0x0000001d_00000000;
+pub const INVOCATION_KEY_PREFIX: u64 = 0x0000001e_00000000;
+pub const AGENT_USAGE_KEY_PREFIX: u64 = 0x0000001f_00000000;
+pub const MUTE_KEY_PREFIX: u64 = 0x00000020_00000000;
+pub const MODERATION_KEY_PREFIX: u64 = 0x00000021_00000000;
+pub const TRANSFER_STATS_KEY_PREFIX: u64 = 0x00000022_00000000;
+pub const AGENT_JOB_KEY_PREFIX: u64 = 0x00000023_00000000;
+pub const SUMMARY_REQUEST_KEY_PREFIX: u64 = 0x00000024_00000000;
+pub const CONSUMED_TX_KEY_PREFIX: u64 = 0x00000025_00000000;
+pub const SUMMARY_QUOTA_KEY_PREFIX: u64 = 0x00000026_00000000;
+pub const REACTION_KEY_PREFIX: u64 = 0x00000027_00000000;
+pub const REACTION_COUNT_KEY_PREFIX: u64 = 0x00000028_00000000;
+pub const PINNED_KEY_PREFIX: u64 = 0x00000029_00000000;
 pub const HTTP_MASK: u128 = 0x0000000f_00000000_00000000_00000000;
 
 pub fn is_comment(content_id: ContentId) -> bool {
@@ -131,3 +160,321 @@ pub fn to_permission_key(community_id: CommunityId, account_id: AccountId) -> [u
     .try_into()
     .unwrap()
 }
+
+pub fn to_followee_bytes(followee: &Followee) -> [u8; 21] {
+    let mut r = [0u8; 21];
+    match followee {
+        Followee::Community(id) => {
+            r[1..5].copy_from_slice(&id.to_be_bytes());
+        }
+        Followee::Account(id) => {
+            r[0] = 1;
+            r[1..].copy_from_slice(&id.0);
+        }
+    }
+    r
+}
+
+pub fn to_followee(raw: &[u8]) -> Result<Followee, String> {
+    match raw.split_first() {
+        Some((0, id)) if id.len() == 20 => Ok(Followee::Community(CommunityId::from_be_bytes(
+            id[..4].try_into().expect("qed"),
+        ))),
+        Some((1, id)) => AccountId::from_slice(id).map(Followee::Account),
+        _ => Err("invalid followee".to_string()),
+    }
+}
+
+pub fn to_following_key(follower: AccountId, followee: &Followee) -> [u8; 49] {
+    [
+        &FOLLOWING_KEY_PREFIX.to_be_bytes()[..],
+        &follower.0[..],
+        &to_followee_bytes(followee)[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_follower_key(followee: &Followee, follower: AccountId) -> [u8; 49] {
+    [
+        &FOLLOWER_KEY_PREFIX.to_be_bytes()[..],
+        &to_followee_bytes(followee)[..],
+        &follower.0[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+/// prefix of the per-author or per-community thread index, ordered by (created_time, thread_id)
+pub fn to_thread_index_prefix(source: &Followee) -> Vec<u8> {
+    match source {
+        Followee::Community(id) => [
+            &COMMUNITY_THREAD_PREFIX.to_be_bytes()[..],
+            &id.to_be_bytes()[..],
+        ]
+        .concat(),
+        Followee::Account(id) => [&AUTHOR_THREAD_PREFIX.to_be_bytes()[..], &id.0[..]].concat(),
+    }
+}
+
+pub fn to_thread_index_key(source: &Followee, created_time: u64, thread_id: ContentId) -> Vec<u8> {
+    [
+        &to_thread_index_prefix(source)[..],
+        &created_time.to_be_bytes()[..],
+        &thread_id.to_be_bytes()[..],
+    ]
+    .concat()
+}
+
+pub fn to_thread_index(key: &[u8]) -> Result<(u64, ContentId), String> {
+    (key.len() >= 24)
+        .then(|| ())
+        .ok_or("invalid thread index".to_string())?;
+    let suffix = &key[key.len() - 24..];
+    let created_time = u64::from_be_bytes(suffix[..8].try_into().expect("qed"));
+    let thread_id = ContentId::from_be_bytes(suffix[8..].try_into().expect("qed"));
+    Ok((created_time, thread_id))
+}
+
+pub fn to_pubkey_key(account_id: AccountId) -> [u8; 28] {
+    [&PUBKEY_KEY_PREFIX.to_be_bytes()[..], &account_id.0[..]]
+        .concat()
+        .try_into()
+        .unwrap()
+}
+
+pub fn to_message_key(conversation: ConversationId, message_id: u64) -> [u8; 32] {
+    [
+        &MESSAGE_KEY_PREFIX.to_be_bytes()[..],
+        &conversation.to_be_bytes()[..],
+        &message_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_conversation_key(account_id: AccountId, conversation: ConversationId) -> [u8; 44] {
+    [
+        &CONVERSATION_KEY_PREFIX.to_be_bytes()[..],
+        &account_id.0[..],
+        &conversation.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_reputation_key(community_id: CommunityId, account_id: AccountId) -> [u8; 32] {
+    [
+        &REPUTATION_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &account_id.0[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+/// the score is biased to keep the order of negative scores
+pub fn to_reputation_rank_key(
+    community_id: CommunityId,
+    score: i64,
+    account_id: AccountId,
+) -> [u8; 40] {
+    [
+        &REPUTATION_RANK_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &((score as u64) ^ (1u64 << 63)).to_be_bytes()[..],
+        &account_id.0[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_activation_key(community_id: CommunityId) -> [u8; 8] {
+    (ACTIVATION_KEY_PREFIX | community_id as u64).to_be_bytes()
+}
+
+pub fn to_admin_key(account_id: AccountId) -> [u8; 28] {
+    [&ADMIN_KEY_PREFIX.to_be_bytes()[..], &account_id.0[..]]
+        .concat()
+        .try_into()
+        .unwrap()
+}
+
+pub fn to_post_stats_key(community_id: CommunityId, account_id: AccountId) -> [u8; 32] {
+    [
+        &POST_STATS_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &account_id.0[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+/// the scope is the thread of comments, or the community of threads as `community_id << 64`
+pub fn to_last_post_key(account_id: AccountId, scope: ContentId) -> [u8; 44] {
+    [
+        &LAST_POST_KEY_PREFIX.to_be_bytes()[..],
+        &account_id.0[..],
+        &scope.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_chat_session_key(thread_id: ContentId) -> [u8; 24] {
+    [
+        &CHAT_SESSION_KEY_PREFIX.to_be_bytes()[..],
+        &thread_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_prompt_history_key(community_id: CommunityId, seq: u32) -> [u8; 16] {
+    [
+        &PROMPT_HISTORY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &seq.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_invocation_key(content_id: ContentId) -> [u8; 24] {
+    [
+        &INVOCATION_KEY_PREFIX.to_be_bytes()[..],
+        &content_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+/// the day `u64::MAX` holds the total usage
+pub fn to_agent_usage_key(community_id: CommunityId, day: u64) -> [u8; 20] {
+    [
+        &AGENT_USAGE_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &day.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_mute_key(community_id: CommunityId, account_id: AccountId) -> [u8; 32] {
+    [
+        &MUTE_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &account_id.0[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_moderation_key(community_id: CommunityId, seq: u32) -> [u8; 16] {
+    [
+        &MODERATION_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &seq.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_transfer_stats_key(community_id: CommunityId) -> [u8; 12] {
+    [
+        &TRANSFER_STATS_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_agent_job_key(community_id: CommunityId, job_id: u32) -> [u8; 16] {
+    [
+        &AGENT_JOB_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &job_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_summary_request_key(thread_id: ContentId) -> [u8; 24] {
+    [
+        &SUMMARY_REQUEST_KEY_PREFIX.to_be_bytes()[..],
+        &thread_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_consumed_tx_key(community_id: CommunityId, tx_hash: [u8; 32]) -> [u8; 44] {
+    [
+        &CONSUMED_TX_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &tx_hash[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_summary_quota_key(community_id: CommunityId, account_id: AccountId) -> [u8; 32] {
+    [
+        &SUMMARY_QUOTA_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &account_id.0[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_reaction_key(content_id: ContentId, account_id: AccountId) -> [u8; 44] {
+    [
+        &REACTION_KEY_PREFIX.to_be_bytes()[..],
+        &content_id.to_be_bytes()[..],
+        &account_id.0[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_reaction_count_key(content_id: ContentId) -> [u8; 24] {
+    [
+        &REACTION_COUNT_KEY_PREFIX.to_be_bytes()[..],
+        &content_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
+
+pub fn to_pinned_key(community_id: CommunityId, thread_id: ContentId) -> [u8; 28] {
+    [
+        &PINNED_KEY_PREFIX.to_be_bytes()[..],
+        &community_id.to_be_bytes()[..],
+        &thread_id.to_be_bytes()[..],
+    ]
+    .concat()
+    .try_into()
+    .unwrap()
+}
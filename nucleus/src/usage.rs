@@ -0,0 +1,63 @@
+use crate::trie;
+use vemodel::*;
+use vrs_core_sdk::timer;
+
+const SECONDS_PER_DAY: u64 = 86400;
+const TOTAL: u64 = u64::MAX;
+
+pub(crate) fn today() -> u64 {
+    timer::now() / SECONDS_PER_DAY
+}
+
+/// the usage of a day, or the total if `day` is `None`
+pub(crate) fn get_usage(community_id: CommunityId, day: Option<u64>) -> Result<AgentUsage, String> {
+    let key = trie::to_agent_usage_key(community_id, day.unwrap_or(TOTAL));
+    crate::find::<AgentUsage>(&key).map(|v| v.unwrap_or_default())
+}
+
+/// the vendors return versioned names like `gpt-4o-2024-08-06`, so the longest
+/// listed prefix is priced, and the unlisted models are counted in tokens only
+fn cost_of(vendor: &LlmVendor, model: &str, prompt_tokens: u64, completion_tokens: u64) -> u64 {
+    crate::get_models()
+        .unwrap_or_default()
+        .iter()
+        .filter(|m| m.vendor == vendor.name() && model.starts_with(&m.model))
+        .max_by_key(|m| m.model.len())
+        .map(|m| {
+            let cost = prompt_tokens as u128 * m.prompt_price as u128
+                + completion_tokens as u128 * m.completion_price as u128;
+            (cost / 1_000_000) as u64
+        })
+        .unwrap_or_default()
+}
+
+pub(crate) fn record(
+    community: &Community,
+    model: &str,
+    prompt_tokens: u64,
+    completion_tokens: u64,
+) -> Result<(), String> {
+    let community_id = community.id();
+    let cost = cost_of(
+        &community.llm_vendor,
+        model,
+        prompt_tokens,
+        completion_tokens,
+    );
+    for day in [today(), TOTAL] {
+        let mut usage = get_usage(community_id, Some(day))?;
+        usage.prompt_tokens += prompt_tokens;
+        usage.completion_tokens += completion_tokens;
+        usage.cost += cost;
+        crate::save(&trie::to_agent_usage_key(community_id, day), &usage)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn is_over_budget(community: &Community) -> Result<bool, String> {
+    let community_id = community.id();
+    let today = get_usage(community_id, Some(today()))?;
+    let total = get_usage(community_id, None)?;
+    Ok(community.agent_budget.is_exceeded(&today, &total)
+        || community.platform_budget.is_exceeded(&today, &total))
+}
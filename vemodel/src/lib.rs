@@ -1,7 +1,7 @@
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
-use vrs_core_sdk::codec::{Decode, Encode};
+use vrs_core_sdk::codec::{Decode, Encode, Error as CodecError, Input};
 
 
 pub type CommunityId = u32;
@@ -24,6 +24,22 @@ pub fn get_belongs_to(content_id: ContentId) -> CommunityId {
     (content_id >> 64) as CommunityId
 }
 
+/// decode a field appended to a stored struct, the records saved before the field
+/// was appended end earlier and get the default
+fn decode_appended<T: Decode + Default, I: Input>(input: &mut I) -> Result<T, CodecError> {
+    decode_appended_or(input, T::default)
+}
+
+fn decode_appended_or<T: Decode, I: Input>(
+    input: &mut I,
+    default: impl FnOnce() -> T,
+) -> Result<T, CodecError> {
+    match input.remaining_len()? {
+        Some(0) => Ok(default()),
+        _ => T::decode(input),
+    }
+}
+
 #[derive(Debug, Decode, Encode, Deserialize, Serialize, Clone, Copy, TypeInfo)]
 pub enum Event {
     #[codec(index = 0)]
@@ -38,18 +54,76 @@ pub enum Event {
     CommentPosted(ContentId),
     #[codec(index = 5)]
     CommentDeleted(ContentId),
+    #[codec(index = 6)]
+    AccountUpdated(AccountId),
+    #[codec(index = 7)]
+    ThreadUpdated(ContentId),
+    #[codec(index = 8)]
+    CommentUpdated(ContentId),
+    #[codec(index = 9)]
+    ThreadSummarized(ContentId),
 }
 
-#[derive(Debug, Decode, Clone, Encode, Deserialize, Serialize, Eq, PartialEq, TypeInfo)]
+/// the index 4 is `Frozen(u64)` saved before the reason was recorded
+#[derive(Debug, Clone, Encode, Deserialize, Serialize, Eq, PartialEq, TypeInfo)]
 pub enum CommunityStatus {
+    #[codec(index = 0)]
     PendingCreation,
+    #[codec(index = 1)]
     WaitingTx(u128),
+    #[codec(index = 2)]
     CreateFailed(String),
+    #[codec(index = 3)]
     Active,
-    Frozen(u64),
+    /// frozen by the platform until the timestamp, with the reason
+    #[codec(index = 6)]
+    Frozen(u64, String),
+    #[codec(index = 5)]
     TokenIssued(String),
 }
 
+impl Decode for CommunityStatus {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        match input.read_byte()? {
+            0 => Ok(Self::PendingCreation),
+            1 => Ok(Self::WaitingTx(Decode::decode(input)?)),
+            2 => Ok(Self::CreateFailed(Decode::decode(input)?)),
+            3 => Ok(Self::Active),
+            4 => Ok(Self::Frozen(Decode::decode(input)?, String::new())),
+            5 => Ok(Self::TokenIssued(Decode::decode(input)?)),
+            6 => Ok(Self::Frozen(Decode::decode(input)?, Decode::decode(input)?)),
+            _ => Err("Invalid CommunityStatus".into()),
+        }
+    }
+}
+
+/// The activation fee received from `payer`, kept until the community is active or refunded.
+#[derive(Debug, Clone, Encode, Deserialize, Serialize, Eq, PartialEq, TypeInfo)]
+pub struct ActivationPayment {
+    pub payer: AccountId,
+    pub amount: u128,
+    pub issue_tx: Option<String>,
+    pub refunding: bool,
+    pub refund_tx: Option<String>,
+    /// the nonce and gas price of `refund_tx`, a tx never mined is replaced with the same nonce
+    pub refund_nonce: Option<u64>,
+    pub refund_gas_price: Option<u64>,
+}
+
+impl Decode for ActivationPayment {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Self {
+            payer: Decode::decode(input)?,
+            amount: Decode::decode(input)?,
+            issue_tx: Decode::decode(input)?,
+            refunding: Decode::decode(input)?,
+            refund_tx: Decode::decode(input)?,
+            refund_nonce: decode_appended(input)?,
+            refund_gas_price: decode_appended(input)?,
+        })
+    }
+}
+
 #[derive(Debug, Decode, Clone, Encode, Deserialize, Serialize, Eq, PartialEq, TypeInfo)]
 pub enum CommunityMode {
     Public,
@@ -57,7 +131,7 @@ pub enum CommunityMode {
     PayToJoin(u128),
 }
 
-#[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+#[derive(Debug, Encode, Deserialize, Serialize, TypeInfo)]
 pub struct Community {
     pub id: String,
     pub mode: CommunityMode,
@@ -76,6 +150,430 @@ pub struct Community {
     pub llm_assistant_id: String,
     pub status: CommunityStatus,
     pub created_time: i64,
+    pub min_post_reputation: Option<i64>,
+    pub pending_owner: Option<AccountId>,
+    pub rate_limit: RateLimit,
+    pub content_rules: Option<ContentRules>,
+    /// the hosts allowed to serve image urls
+    pub image_hosts: Vec<String>,
+    pub generation: GenerationParams,
+    /// the names of tools enabled for the agent
+    pub agent_tools: Vec<String>,
+    /// set by the creator
+    pub agent_budget: AgentBudget,
+    /// set by the platform admins
+    pub platform_budget: AgentBudget,
+    pub transfer_policy: TransferPolicy,
+    /// the tokens paid to the agent for a thread summary, free if `None`
+    pub summary_price: Option<u128>,
+    /// the reputation for tagging threads besides the moderators, only moderators if `None`
+    pub min_tag_reputation: Option<i64>,
+}
+
+/// the tools of the agents created before the tools were configurable
+const LEGACY_AGENT_TOOLS: [&str; 3] = ["transfer", "agent_balance", "balance_of"];
+
+/// the fields after `created_time` are appended, new fields should be appended as well
+impl Decode for Community {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Self {
+            id: Decode::decode(input)?,
+            mode: Decode::decode(input)?,
+            logo: Decode::decode(input)?,
+            name: Decode::decode(input)?,
+            slug: Decode::decode(input)?,
+            description: Decode::decode(input)?,
+            token_info: Decode::decode(input)?,
+            agent_contract: Decode::decode(input)?,
+            prompt: Decode::decode(input)?,
+            platform_bnb_benefit: Decode::decode(input)?,
+            creator_bnb_benefit: Decode::decode(input)?,
+            creator: Decode::decode(input)?,
+            agent_pubkey: Decode::decode(input)?,
+            llm_vendor: Decode::decode(input)?,
+            llm_assistant_id: Decode::decode(input)?,
+            status: Decode::decode(input)?,
+            created_time: Decode::decode(input)?,
+            min_post_reputation: decode_appended(input)?,
+            pending_owner: decode_appended(input)?,
+            rate_limit: decode_appended(input)?,
+            content_rules: decode_appended(input)?,
+            image_hosts: decode_appended(input)?,
+            generation: decode_appended(input)?,
+            agent_tools: decode_appended_or(input, || {
+                LEGACY_AGENT_TOOLS.iter().map(|t| t.to_string()).collect()
+            })?,
+            agent_budget: decode_appended(input)?,
+            platform_budget: decode_appended(input)?,
+            transfer_policy: decode_appended(input)?,
+            summary_price: decode_appended(input)?,
+            min_tag_reputation: decode_appended(input)?,
+        })
+    }
+}
+
+/// the parameters of LLM generation, `None` to use the defaults of the vendor,
+/// temperature and top_p are in thousandths since floats are not portable
+#[derive(Debug, Clone, Default, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct GenerationParams {
+    pub model: Option<String>,
+    pub temperature: Option<u16>,
+    pub top_p: Option<u16>,
+    pub max_tokens: Option<u32>,
+}
+
+impl GenerationParams {
+    pub fn validate(&self) -> Result<(), String> {
+        (self.temperature.unwrap_or_default() <= 2000)
+            .then(|| ())
+            .ok_or("temperature should be no more than 2000(2.0)".to_string())?;
+        (self.top_p.unwrap_or_default() <= 1000)
+            .then(|| ())
+            .ok_or("top_p should be no more than 1000(1.0)".to_string())?;
+        (self.max_tokens != Some(0))
+            .then(|| ())
+            .ok_or("max_tokens should be greater than 0".to_string())
+    }
+}
+
+/// the replaced prompt of an agent
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct PromptRevision {
+    pub prompt: String,
+    pub replaced_time: i64,
+}
+
+/// the models allowed on the platform hosts, prices are in micro USD per million tokens
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct ModelPrice {
+    pub vendor: String,
+    pub model: String,
+    pub prompt_price: u64,
+    pub completion_price: u64,
+}
+
+/// the posting limits of a community, 0 means unlimited
+#[derive(Debug, Clone, Default, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct RateLimit {
+    /// the seconds between two posts, `None` to follow the platform
+    pub cooldown: Option<u64>,
+    pub daily_posts: u32,
+    pub daily_comments: u32,
+    pub exempt_moderators: bool,
+}
+
+/// the limits of posts and comments, the defaults are also the caps of community overrides
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct ContentRules {
+    pub max_title_len: u32,
+    /// the max bytes of the decompressed content
+    pub max_content_len: u32,
+    pub max_mentions: u32,
+    pub max_images: u32,
+    /// reject the exact duplicate of the author's last post
+    pub reject_duplicate: bool,
+}
+
+impl Default for ContentRules {
+    fn default() -> Self {
+        Self {
+            max_title_len: 200,
+            max_content_len: 64 * 1024,
+            max_mentions: 20,
+            max_images: 9,
+            reject_duplicate: true,
+        }
+    }
+}
+
+impl ContentRules {
+    pub fn validate_within(&self, caps: &ContentRules) -> Result<(), String> {
+        (self.max_title_len > 0 && self.max_content_len > 0)
+            .then(|| ())
+            .ok_or("The limits of title and content should be greater than 0".to_string())?;
+        (self.max_title_len <= caps.max_title_len
+            && self.max_content_len <= caps.max_content_len
+            && self.max_mentions <= caps.max_mentions
+            && self.max_images <= caps.max_images)
+            .then(|| ())
+            .ok_or("The content rules exceed the platform limits".to_string())
+    }
+
+    fn validate_content(
+        &self,
+        content: &[u8],
+        mention: &[AccountId],
+        images: &[Image],
+        image_hosts: &[String],
+    ) -> Result<(), String> {
+        (!content.is_empty())
+            .then(|| ())
+            .ok_or("The content shouldn't be empty".to_string())?;
+        (content.len() <= self.max_content_len as usize)
+            .then(|| ())
+            .ok_or(format!(
+                "The content should be no more than {} bytes",
+                self.max_content_len
+            ))?;
+        (mention.len() <= self.max_mentions as usize)
+            .then(|| ())
+            .ok_or(format!(
+                "No more than {} accounts could be mentioned",
+                self.max_mentions
+            ))?;
+        (images.len() <= self.max_images as usize)
+            .then(|| ())
+            .ok_or(format!("No more than {} images are allowed", self.max_images))?;
+        images.iter().try_for_each(|image| image.validate(image_hosts))
+    }
+}
+
+const IMAGE_MIMES: [&str; 4] = ["image/png", "image/jpeg", "image/gif", "image/webp"];
+const MAX_IMAGE_SIZE: u64 = 10 * 1024 * 1024;
+const IPFS_CID_REGEX: &str = r"^(Qm[1-9A-HJ-NP-Za-km-z]{44}|b[a-z2-7]{58,})$";
+
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub enum ImageSource {
+    /// https url served by one of the allowed hosts
+    Url(String),
+    Ipfs(String),
+    /// the content hash of an image stored elsewhere, never forwarded
+    Sha256([u8; 32]),
+}
+
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct Image {
+    pub source: ImageSource,
+    pub mime: String,
+    pub size: u64,
+}
+
+impl Image {
+    pub fn validate(&self, image_hosts: &[String]) -> Result<(), String> {
+        IMAGE_MIMES
+            .contains(&self.mime.as_str())
+            .then(|| ())
+            .ok_or(format!("Unsupported image type: {}", self.mime))?;
+        (self.size > 0 && self.size <= MAX_IMAGE_SIZE)
+            .then(|| ())
+            .ok_or(format!(
+                "The image should be no more than {} bytes",
+                MAX_IMAGE_SIZE
+            ))?;
+        match &self.source {
+            ImageSource::Url(url) => {
+                let host = url_host(url).ok_or(format!("Invalid image url: {}", url))?;
+                image_hosts
+                    .iter()
+                    .any(|h| h.eq_ignore_ascii_case(host))
+                    .then(|| ())
+                    .ok_or(format!("The image host {} is not allowed", host))
+            }
+            ImageSource::Ipfs(cid) => {
+                let re = regex::Regex::new(IPFS_CID_REGEX).unwrap();
+                re.is_match(cid)
+                    .then(|| ())
+                    .ok_or(format!("Invalid IPFS cid: {}", cid))
+            }
+            ImageSource::Sha256(_) => Ok(()),
+        }
+    }
+
+    /// the url which could be fetched by others, `None` for hashes
+    pub fn public_url(&self, ipfs_gateway: &str) -> Option<String> {
+        match &self.source {
+            ImageSource::Url(url) => Some(url.clone()),
+            ImageSource::Ipfs(cid) => Some(format!("{}/ipfs/{}", ipfs_gateway, cid)),
+            ImageSource::Sha256(_) => None,
+        }
+    }
+}
+
+/// the host of a https url without userinfo
+fn url_host(url: &str) -> Option<&str> {
+    let rest = url.strip_prefix("https://")?;
+    let host = rest.split(['/', '?', '#']).next()?;
+    let host = host.split(':').next()?;
+    (!host.is_empty() && !host.contains('@') && !url.contains(char::is_whitespace))
+        .then(|| host)
+}
+
+/// the tokens consumed by the agent, the cost is in micro USD
+#[derive(Debug, Clone, Default, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct AgentUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost: u64,
+}
+
+/// the agent is paused once the cost exceeds a budget, in micro USD, `None` for no limit
+#[derive(Debug, Clone, Default, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct AgentBudget {
+    pub daily: Option<u64>,
+    pub total: Option<u64>,
+}
+
+impl AgentBudget {
+    pub fn is_exceeded(&self, today: &AgentUsage, total: &AgentUsage) -> bool {
+        self.daily.map(|b| today.cost >= b).unwrap_or_default()
+            || self.total.map(|b| total.cost >= b).unwrap_or_default()
+    }
+}
+
+/// the limits of the agent transferring the community tokens in whole tokens like the `transfer`
+/// tool, `None` for no limit
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct TransferPolicy {
+    pub max_per_transfer: Option<u128>,
+    pub max_per_recipient_daily: Option<u128>,
+    pub max_daily_total: Option<u128>,
+    /// forbid transferring to the authors of the thread and the comment in the run
+    pub block_self_dealing: bool,
+}
+
+impl Default for TransferPolicy {
+    fn default() -> Self {
+        // the agent is capped until the community sets its own policy
+        Self {
+            max_per_transfer: Some(1_000),
+            max_per_recipient_daily: Some(2_000),
+            max_daily_total: Some(10_000),
+            block_self_dealing: true,
+        }
+    }
+}
+
+/// a thread posted by the agent every `interval` seconds, following the instruction;
+/// the runs are a fixed interval apart from the first one, cron expressions like
+/// "every monday at 9" are not supported
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct AgentJob {
+    pub id: u32,
+    pub title: String,
+    pub instruction: String,
+    pub interval: u64,
+    /// the timestamp of the next run
+    pub next_run: u64,
+}
+
+/// a pending thread summary, the payment is refunded if it fails
+#[derive(Debug, Clone, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct SummaryRequest {
+    pub requester: AccountId,
+    pub paid: u128,
+    /// the time of the latest call to the LLM
+    pub requested_at: u64,
+    /// the comments are summarized in chunks, `partial` is the summary up to `cursor`
+    pub cursor: Option<ContentId>,
+    pub partial: Option<String>,
+}
+
+impl Decode for SummaryRequest {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Self {
+            requester: Decode::decode(input)?,
+            paid: Decode::decode(input)?,
+            requested_at: Decode::decode(input)?,
+            cursor: decode_appended(input)?,
+            partial: decode_appended(input)?,
+        })
+    }
+}
+
+/// the tokens transferred by the agent in a day
+#[derive(Debug, Clone, Default, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct TransferStats {
+    pub day: u64,
+    pub total: u128,
+    pub recipients: Vec<(AccountId, u128)>,
+}
+
+impl TransferStats {
+    pub fn received(&self, recipient: &AccountId) -> u128 {
+        self.recipients
+            .iter()
+            .find(|(a, _)| a == recipient)
+            .map(|(_, v)| *v)
+            .unwrap_or_default()
+    }
+}
+
+/// the moderation taken by the agent, with the states before it to be reverted
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub enum ModerationAction {
+    HideComment {
+        comment: ContentId,
+        hidden: bool,
+        previous: bool,
+    },
+    DeleteComment {
+        comment: ContentId,
+        content: Vec<u8>,
+        hidden: bool,
+    },
+    LockThread {
+        thread: ContentId,
+        locked: bool,
+        previous: bool,
+    },
+    PinThread {
+        thread: ContentId,
+        pinned: bool,
+        previous: bool,
+    },
+    MuteUser {
+        account: AccountId,
+        until: u64,
+        previous: Option<u64>,
+    },
+    TagThread {
+        thread: ContentId,
+        tags: Vec<String>,
+        previous: Vec<String>,
+    },
+}
+
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct ModerationRecord {
+    pub id: u32,
+    pub action: ModerationAction,
+    /// the LLM run which triggered the action
+    pub run_id: String,
+    pub created_time: i64,
+    pub reverted: bool,
+}
+
+impl ModerationRecord {
+    pub fn mask(&mut self) {
+        if let ModerationAction::DeleteComment { content, .. } = &mut self.action {
+            *content = Default::default();
+        }
+    }
+}
+
+/// the posting records of an account in a community
+#[derive(Debug, Clone, Default, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct PostStats {
+    pub last_post_at: u64,
+    pub day: u64,
+    pub posts: u32,
+    pub comments: u32,
+    /// counted since the totals were recorded
+    pub total_threads: u64,
+    pub total_comments: u64,
+}
+
+impl Decode for PostStats {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Self {
+            last_post_at: Decode::decode(input)?,
+            day: Decode::decode(input)?,
+            posts: Decode::decode(input)?,
+            comments: Decode::decode(input)?,
+            total_threads: decode_appended(input)?,
+            total_comments: decode_appended(input)?,
+        })
+    }
 }
 
 impl Community {
@@ -86,12 +584,17 @@ impl Community {
         CommunityId::from_be_bytes(v[..4].try_into().unwrap())
     }
 
+    pub fn content_rules(&self) -> ContentRules {
+        self.content_rules.clone().unwrap_or_default()
+    }
+
     pub fn mask(&mut self) {
         self.prompt = Default::default();
         match &self.llm_vendor {
-            LlmVendor::OpenAI { .. } => {
+            LlmVendor::OpenAI { key: _key, host } => {
                 self.llm_vendor = LlmVendor::OpenAI {
                     key: Default::default(),
+                    host: host.clone(),
                 };
             }
             LlmVendor::DeepSeek { key: _key, host } => {
@@ -105,17 +608,52 @@ impl Community {
 }
 
 
-#[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+#[derive(Debug, Encode, Deserialize, Serialize, TypeInfo)]
 pub struct Thread {
     pub id: String,
     pub community_name: String,
     pub title: String,
     pub content: Vec<u8>,
-    pub images: Vec<String>,
+    /// the unchecked image urls of the threads posted before `images`
+    pub legacy_images: Vec<String>,
     pub author: AccountId,
     pub mention: Vec<AccountId>,
     pub llm_session_id: String,
     pub created_time: i64,
+    pub images: Vec<Image>,
+    /// no more comments except from the moderators
+    pub locked: bool,
+    pub pinned: bool,
+    pub tags: Vec<String>,
+    /// generated by the agent on demand
+    pub summary: Option<String>,
+    pub summarized_at: Option<i64>,
+    /// the comment accepted by the author with its bounty
+    pub accepted_answer: Option<(String, u128)>,
+}
+
+/// the fields after `created_time` are appended, new fields should be appended as well
+impl Decode for Thread {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Self {
+            id: Decode::decode(input)?,
+            community_name: Decode::decode(input)?,
+            title: Decode::decode(input)?,
+            content: Decode::decode(input)?,
+            legacy_images: Decode::decode(input)?,
+            author: Decode::decode(input)?,
+            mention: Decode::decode(input)?,
+            llm_session_id: Decode::decode(input)?,
+            created_time: Decode::decode(input)?,
+            images: decode_appended(input)?,
+            locked: decode_appended(input)?,
+            pinned: decode_appended(input)?,
+            tags: decode_appended(input)?,
+            summary: decode_appended(input)?,
+            summarized_at: decode_appended(input)?,
+            accepted_answer: decode_appended(input)?,
+        })
+    }
 }
 
 impl Thread {
@@ -127,17 +665,47 @@ impl Thread {
     pub fn community_id(&self) -> CommunityId {
         (self.id() >> 64) as CommunityId
     }
+
+    pub fn envelope(&self) -> Result<ContentEnvelope, String> {
+        ContentEnvelope::parse(&self.content).map(|(e, _)| e)
+    }
 }
 
-#[derive(Debug, Decode, Encode, Deserialize, Serialize)]
+#[derive(Debug, Encode, Deserialize, Serialize)]
 pub struct Comment {
     pub id: String,
     pub content: Vec<u8>,
-    pub images: Vec<String>,
+    /// the unchecked image urls of the comments posted before `images`
+    pub legacy_images: Vec<String>,
     pub author: AccountId,
     pub mention: Vec<AccountId>,
     pub reply_to: Option<String>,
     pub created_time: i64,
+    pub images: Vec<Image>,
+    pub hidden: bool,
+}
+
+/// the fields after `created_time` are appended, new fields should be appended as well
+impl Decode for Comment {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Self {
+            id: Decode::decode(input)?,
+            content: Decode::decode(input)?,
+            legacy_images: Decode::decode(input)?,
+            author: Decode::decode(input)?,
+            mention: Decode::decode(input)?,
+            reply_to: Decode::decode(input)?,
+            created_time: Decode::decode(input)?,
+            images: decode_appended(input)?,
+            hidden: decode_appended(input)?,
+        })
+    }
+}
+
+impl Comment {
+    pub fn envelope(&self) -> Result<ContentEnvelope, String> {
+        ContentEnvelope::parse(&self.content).map(|(e, _)| e)
+    }
 }
 
 impl Comment {
@@ -156,6 +724,53 @@ impl Comment {
     }
 }
 
+#[derive(Debug, Clone, Copy, Decode, Encode, Deserialize, Serialize, Eq, PartialEq, TypeInfo)]
+pub enum Followee {
+    Community(CommunityId),
+    Account(AccountId),
+}
+
+/// Position of the last thread returned by `get_feed`, pass it back to fetch older threads.
+#[derive(Debug, Clone, Copy, Decode, Encode, Deserialize, Serialize, Eq, PartialEq, TypeInfo)]
+pub struct FeedCursor {
+    pub created_time: i64,
+    pub thread: ContentId,
+}
+
+pub type ConversationId = u128;
+
+/// the conversation between two accounts, which is independent of the order of the participants
+pub fn conversation_id(a: AccountId, b: AccountId) -> ConversationId {
+    let (first, second) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+    let mut hasher = Sha256::new();
+    hasher.update(first.0);
+    hasher.update(second.0);
+    let v = hasher.finalize();
+    ConversationId::from_be_bytes(v[..16].try_into().unwrap())
+}
+
+/// A direct message, the nucleus never sees the plaintext.
+/// `ciphertext` is encrypted(ECIES) to the recipient's public key while `sender_ciphertext`
+/// is an optional copy encrypted to the sender's public key so that the sender could read it later.
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct DirectMessage {
+    pub id: u64,
+    pub conversation: ConversationId,
+    pub sender: AccountId,
+    pub recipient: AccountId,
+    pub ciphertext: Vec<u8>,
+    pub sender_ciphertext: Vec<u8>,
+    pub created_time: i64,
+}
+
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct Conversation {
+    pub id: ConversationId,
+    pub peer: AccountId,
+    pub last_message_id: u64,
+    pub updated_time: i64,
+}
+
 pub type AccountId = H160;
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Decode, Encode, TypeInfo)]
@@ -251,13 +866,36 @@ impl serde::Serialize for H160 {
     }
 }
 
-#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+#[derive(Debug, Clone, Encode, Deserialize, Serialize, TypeInfo)]
 pub struct Account {
     pub nonce: u64,
     pub address: H160,
+    /// the latest payment accepted before the payment txs were recorded
     pub last_transfer_block: u64,
     pub alias: Option<String>,
+    /// no longer updated, the posts are limited per community by `PostStats`
     pub last_post_at: u64,
+    pub avatar: Option<String>,
+    pub bio: Option<String>,
+    pub links: Vec<String>,
+    pub language: Option<String>,
+}
+
+/// the profile fields are appended after `last_post_at`
+impl Decode for Account {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Self {
+            nonce: Decode::decode(input)?,
+            address: Decode::decode(input)?,
+            last_transfer_block: Decode::decode(input)?,
+            alias: Decode::decode(input)?,
+            last_post_at: Decode::decode(input)?,
+            avatar: decode_appended(input)?,
+            bio: decode_appended(input)?,
+            links: decode_appended(input)?,
+            language: decode_appended(input)?,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
@@ -266,8 +904,6 @@ pub enum AccountData {
     AliasOf(AccountId),
 }
 
-const POST_COOLING_DOWN: u64 = 180;
-
 impl Account {
     pub fn new(address: H160) -> Self {
         Self {
@@ -276,6 +912,10 @@ impl Account {
             last_transfer_block: 0,
             alias: None,
             last_post_at: 0,
+            avatar: None,
+            bio: None,
+            links: vec![],
+            language: None,
         }
     }
 
@@ -284,9 +924,44 @@ impl Account {
             .clone()
             .unwrap_or_else(|| self.address.to_string())
     }
+}
 
-    pub fn allow_post(&self, now: u64) -> bool {
-        self.last_post_at + POST_COOLING_DOWN < now
+/// the platform parameters which could be changed by admins without redeploying
+#[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub struct PlatformConfig {
+    pub activate_fee: u128,
+    pub invite_fee: u128,
+    /// the share of PayToJoin fees belonging to the creator, in permille
+    pub creator_share: u16,
+    /// the seconds between two posts, unless overridden by the community
+    pub post_cooling_down: u64,
+}
+
+impl Default for PlatformConfig {
+    fn default() -> Self {
+        Self {
+            activate_fee: 2_000_000_000_000_000,
+            invite_fee: 2_000_000_000_000_000,
+            creator_share: 700,
+            post_cooling_down: 180,
+        }
+    }
+}
+
+impl PlatformConfig {
+    pub fn validate(&self) -> Result<(), String> {
+        (self.activate_fee > 0 && self.invite_fee > 0)
+            .then(|| ())
+            .ok_or("The fees should be greater than 0".to_string())?;
+        (self.creator_share <= 1000)
+            .then(|| ())
+            .ok_or("The creator share should be no more than 1000 permille".to_string())
+    }
+
+    /// split a PayToJoin fee into (creator share, platform share)
+    pub fn split(&self, amount: u64) -> (u64, u64) {
+        let creator = (amount as u128 * self.creator_share as u128 / 1000) as u64;
+        (creator, amount - creator)
     }
 }
 
@@ -301,19 +976,227 @@ pub struct TokenMetadata {
     pub image: Option<String>,
 }
 
-#[derive(Debug, Clone, Decode, Encode, Serialize, Deserialize, TypeInfo)]
+/// the index 0 is `OpenAI { key }` saved before the host was configurable
+#[derive(Debug, Clone, Encode, Serialize, Deserialize, TypeInfo)]
 pub enum LlmVendor {
-    OpenAI { key: String },
+    /// the host is the base url of the api, e.g. `https://api.openai.com/v1`
+    #[codec(index = 2)]
+    OpenAI { key: String, host: String },
+    #[codec(index = 1)]
     DeepSeek { key: String, host: String },
 }
 
+impl Decode for LlmVendor {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        match input.read_byte()? {
+            0 => Ok(Self::OpenAI {
+                key: Decode::decode(input)?,
+                host: Self::LEGACY_OPENAI_HOST.to_string(),
+            }),
+            1 => Ok(Self::DeepSeek {
+                key: Decode::decode(input)?,
+                host: Decode::decode(input)?,
+            }),
+            2 => Ok(Self::OpenAI {
+                key: Decode::decode(input)?,
+                host: Decode::decode(input)?,
+            }),
+            _ => Err("Invalid LlmVendor".into()),
+        }
+    }
+}
+
 impl LlmVendor {
+    const LEGACY_OPENAI_HOST: &str = "https://api.openai.com/v1";
+
     pub fn key<'a>(&'a self) -> &'a str {
         match self {
-            Self::OpenAI { key } => key,
+            Self::OpenAI { key, .. } => key,
             Self::DeepSeek { key, .. } => key,
         }
     }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::OpenAI { .. } => "OpenAI",
+            Self::DeepSeek { .. } => "DeepSeek",
+        }
+    }
+
+    pub fn host(&self) -> &str {
+        match self {
+            Self::OpenAI { host, .. } => host,
+            Self::DeepSeek { host, .. } => host,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub enum ContentFormat {
+    Plain,
+    Markdown,
+    /// structured blocks in JSON
+    Blocks,
+}
+
+impl core::str::FromStr for ContentFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "plain" => Ok(Self::Plain),
+            "markdown" => Ok(Self::Markdown),
+            "blocks" => Ok(Self::Blocks),
+            _ => Err(format!("Unknown content format: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl core::str::FromStr for Compression {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_ref() {
+            "none" => Ok(Self::None),
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            _ => Err(format!("Unknown compression: {}", s)),
+        }
+    }
+}
+
+/// the envelope of `Thread::content` and `Comment::content`, the raw bytes are
+/// `b"VE" ++ version ++ format ++ compression ++ payload`,
+/// the legacy records are gzipped markdown without the header
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ContentEnvelope {
+    pub format: ContentFormat,
+    pub compression: Compression,
+}
+
+impl ContentEnvelope {
+    pub const MAGIC: [u8; 2] = *b"VE";
+    pub const VERSION: u8 = 1;
+    const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+    /// split the raw content into the envelope and the payload
+    pub fn parse(content: &[u8]) -> Result<(Self, &[u8]), String> {
+        if content.starts_with(&Self::GZIP_MAGIC) {
+            let legacy = Self {
+                format: ContentFormat::Markdown,
+                compression: Compression::Gzip,
+            };
+            return Ok((legacy, content));
+        }
+        (content.len() >= 5 && content.starts_with(&Self::MAGIC) && content[2] == Self::VERSION)
+            .then(|| ())
+            .ok_or("Unknown content envelope".to_string())?;
+        let format = match content[3] {
+            0 => ContentFormat::Plain,
+            1 => ContentFormat::Markdown,
+            2 => ContentFormat::Blocks,
+            _ => return Err("Unknown content format".to_string()),
+        };
+        let compression = match content[4] {
+            0 => Compression::None,
+            1 => Compression::Gzip,
+            2 => Compression::Zstd,
+            _ => return Err("Unknown compression".to_string()),
+        };
+        Ok((
+            Self {
+                format,
+                compression,
+            },
+            &content[5..],
+        ))
+    }
+
+    pub fn header(&self) -> [u8; 5] {
+        [
+            Self::MAGIC[0],
+            Self::MAGIC[1],
+            Self::VERSION,
+            self.format as u8,
+            self.compression as u8,
+        ]
+    }
+}
+
+#[cfg(feature = "codec")]
+pub mod codec {
+    use super::{Compression, ContentEnvelope, ContentFormat};
+    use std::io::{Read, Write};
+
+    /// the zstd decoder allocates the declared window before decoding
+    const MAX_ZSTD_WINDOW: u64 = 8 * 1024 * 1024;
+
+    /// decode the content, no more than `limit` bytes would be decompressed to avoid zip bombs
+    pub fn decode(content: &[u8], limit: usize) -> Result<(ContentFormat, String), String> {
+        let (envelope, payload) = ContentEnvelope::parse(content)?;
+        let reader: Box<dyn Read + '_> = match envelope.compression {
+            Compression::None => Box::new(payload),
+            Compression::Gzip => Box::new(flate2::read::GzDecoder::new(payload)),
+            Compression::Zstd => {
+                let (frame, _) = ruzstd::frame::read_frame_header(payload)
+                    .map_err(|e| format!("Invalid compressed data: {:?}", e))?;
+                let window = frame
+                    .header
+                    .window_size()
+                    .map_err(|e| format!("Invalid compressed data: {:?}", e))?;
+                (window <= MAX_ZSTD_WINDOW)
+                    .then(|| ())
+                    .ok_or("The compression window is too large".to_string())?;
+                Box::new(
+                    ruzstd::StreamingDecoder::new(payload)
+                        .map_err(|e| format!("Invalid compressed data: {:?}", e))?,
+                )
+            }
+        };
+        let mut s = String::new();
+        reader
+            .take(limit as u64 + 1)
+            .read_to_string(&mut s)
+            .map_err(|e| format!("Invalid compressed data: {:?}", e))?;
+        (s.len() <= limit)
+            .then(|| ())
+            .ok_or(format!("The content should be no more than {} bytes", limit))?;
+        Ok((envelope.format, s))
+    }
+
+    /// zstd is decoded only
+    pub fn encode(
+        text: &str,
+        format: ContentFormat,
+        compression: Compression,
+    ) -> Result<Vec<u8>, String> {
+        let envelope = ContentEnvelope {
+            format,
+            compression,
+        };
+        let mut r = envelope.header().to_vec();
+        match compression {
+            Compression::None => r.extend_from_slice(text.as_bytes()),
+            Compression::Gzip => {
+                let mut encoder = flate2::write::GzEncoder::new(r, flate2::Compression::default());
+                encoder
+                    .write_all(text.as_bytes())
+                    .map_err(|e| format!("Invalid data: {:?}", e))?;
+                r = encoder
+                    .finish()
+                    .map_err(|e| format!("Invalid compressed data: {:?}", e))?;
+            }
+            Compression::Zstd => return Err("Zstd encoding is not supported".to_string()),
+        }
+        Ok(r)
+    }
 }
 
 #[cfg(feature = "crypto")]
@@ -362,11 +1245,18 @@ pub mod crypto {
     pub trait EcdsaVerifiable<T: Encode> {
         fn ensure_signed(&self, nonce: u64) -> Result<(), String>;
 
+        /// verify the signature like `ensure_signed` and return the uncompressed public key(without 0x04) of the signer
+        fn recover_signer(&self, nonce: u64) -> Result<[u8; 64], String>;
+
         fn to_be_signed(&self) -> Vec<u8>;
     }
 
     impl<T: Encode> EcdsaVerifiable<T> for crate::args::Args<T, EcdsaSignature> {
         fn ensure_signed(&self, nonce: u64) -> Result<(), String> {
+            self.recover_signer(nonce).map(|_| ())
+        }
+
+        fn recover_signer(&self, nonce: u64) -> Result<[u8; 64], String> {
             (self.nonce == nonce)
                 .then(|| ())
                 .ok_or("invalid nonce".to_string())?;
@@ -394,7 +1284,7 @@ pub mod crypto {
             (pubkey_hash[12..] == self.signer.0)
                 .then(|| ())
                 .ok_or("Invalid signature".to_string())?;
-            Ok(())
+            Ok(raw_pubkey)
         }
 
         fn to_be_signed(&self) -> Vec<u8> {
@@ -423,9 +1313,21 @@ pub mod args {
     use parity_scale_codec::{Decode, Encode};
     use serde::{Deserialize, Serialize};
 
-    const COMMUNITY_REGEX: &'static str = r"^[a-zA-Z0-9_-]{3,24}$";
-    const TOKEN_REGEX: &'static str = r"^[a-zA-Z0-9]{3,8}$";
-    const NAME_REGEX: &'static str = r"^[\p{L}\p{N}_-]{3,30}$";
+    const COMMUNITY_REGEX: &str = r"^[a-zA-Z0-9_-]{3,24}$";
+    const TOKEN_REGEX: &str = r"^[a-zA-Z0-9]{3,8}$";
+    const NAME_REGEX: &str = r"^[\p{L}\p{N}_-]{3,30}$";
+    const MAX_MESSAGE_SIZE: usize = 8192;
+    const URL_REGEX: &str = r"^https?://[^\s/$.?#][^\s]*$";
+    const LANGUAGE_REGEX: &str = r"^[a-z]{2,3}(-[a-zA-Z0-9]{2,8})*$";
+    const MAX_URL_LEN: usize = 256;
+    const MAX_BIO_LEN: usize = 500;
+    const MAX_PROFILE_LINKS: usize = 5;
+    const HOST_REGEX: &str =
+        r"^([a-zA-Z0-9]([a-zA-Z0-9-]{0,61}[a-zA-Z0-9])?\.)+[a-zA-Z]{2,63}$";
+    const MAX_IMAGE_HOSTS: usize = 20;
+    const MIN_JOB_INTERVAL: u64 = 3600;
+    const MAX_JOB_INTERVAL: u64 = 30 * 86400;
+    const MAX_JOB_INSTRUCTION_LEN: usize = 4000;
 
     #[derive(Debug, Clone, Decode, Encode, TypeInfo)]
     pub struct Args<T, S> {
@@ -447,6 +1349,7 @@ pub mod args {
         pub llm_name: String,
         pub llm_api_host: Option<String>,
         pub llm_key: Option<String>,
+        pub generation: GenerationParams,
     }
 
     #[derive(Debug, Clone, Decode, Encode, Deserialize, Serialize, TypeInfo)]
@@ -486,6 +1389,7 @@ pub mod args {
             let re = regex::Regex::new(COMMUNITY_REGEX).unwrap();
             re.captures(&self.name)
                 .ok_or("Invalid community name".to_string())?;
+            self.generation.validate()?;
             self.token.validate()
         }
     }
@@ -507,10 +1411,30 @@ pub mod args {
         pub community: String,
         pub title: String,
         pub content: Vec<u8>,
-        pub images: Vec<String>,
+        pub images: Vec<Image>,
         pub mention: Vec<AccountId>,
     }
 
+    impl PostThreadArg {
+        pub fn validate(
+            &self,
+            rules: &ContentRules,
+            image_hosts: &[String],
+        ) -> Result<(), String> {
+            let title = self.title.trim();
+            (!title.is_empty())
+                .then(|| ())
+                .ok_or("The title shouldn't be empty".to_string())?;
+            (title.chars().count() <= rules.max_title_len as usize)
+                .then(|| ())
+                .ok_or(format!(
+                    "The title should be no more than {} characters",
+                    rules.max_title_len
+                ))?;
+            rules.validate_content(&self.content, &self.mention, &self.images, image_hosts)
+        }
+    }
+
     #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
     pub struct GenerateInviteTicketArgs {
         pub community_id: CommunityId,
@@ -527,11 +1451,21 @@ pub mod args {
     pub struct PostCommentArg {
         pub thread: ContentId,
         pub content: Vec<u8>,
-        pub images: Vec<String>,
+        pub images: Vec<Image>,
         pub mention: Vec<AccountId>,
         pub reply_to: Option<ContentId>,
     }
 
+    impl PostCommentArg {
+        pub fn validate(
+            &self,
+            rules: &ContentRules,
+            image_hosts: &[String],
+        ) -> Result<(), String> {
+            rules.validate_content(&self.content, &self.mention, &self.images, image_hosts)
+        }
+    }
+
     #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
     pub struct SetAliasArg {
         pub alias: String,
@@ -546,6 +1480,321 @@ pub mod args {
         }
     }
 
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct FollowArg {
+        pub followee: Followee,
+    }
+
+    /// the public key is recovered from the signature, nothing else is required
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct RegisterPubkeyArg {}
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SendMessageArg {
+        pub recipient: AccountId,
+        pub ciphertext: Vec<u8>,
+        pub sender_ciphertext: Vec<u8>,
+    }
+
+    impl SendMessageArg {
+        pub fn validate(&self) -> Result<(), String> {
+            (!self.ciphertext.is_empty())
+                .then(|| ())
+                .ok_or("Empty message".to_string())?;
+            (self.ciphertext.len() <= MAX_MESSAGE_SIZE
+                && self.sender_ciphertext.len() <= MAX_MESSAGE_SIZE)
+                .then(|| ())
+                .ok_or(format!(
+                    "message should be no more than {} bytes",
+                    MAX_MESSAGE_SIZE
+                ))?;
+            Ok(())
+        }
+    }
+
+    /// the signed queries are never stored, `expire_at` is in seconds and limits their replay
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct GetConversationArg {
+        pub peer: AccountId,
+        pub before: Option<u64>,
+        pub limit: u32,
+        pub expire_at: u64,
+    }
+
+    /// the replaced prompts are only listed to the creator and the platform admins
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct GetPromptHistoryArg {
+        pub community: String,
+        pub before: Option<u32>,
+        pub limit: u32,
+        pub expire_at: u64,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct ListConversationsArg {
+        pub gt: Option<ConversationId>,
+        pub limit: u32,
+        pub expire_at: u64,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetProfileArg {
+        pub avatar: Option<String>,
+        pub bio: Option<String>,
+        pub links: Vec<String>,
+        pub language: Option<String>,
+    }
+
+    fn validate_url(url: &str) -> Result<(), String> {
+        let re = regex::Regex::new(URL_REGEX).unwrap();
+        (url.len() <= MAX_URL_LEN && re.is_match(url))
+            .then(|| ())
+            .ok_or(format!("Invalid url: {}", url))
+    }
+
+    impl SetProfileArg {
+        pub fn validate(&self) -> Result<(), String> {
+            if let Some(avatar) = &self.avatar {
+                validate_url(avatar)?;
+            }
+            if let Some(bio) = &self.bio {
+                (bio.chars().count() <= MAX_BIO_LEN)
+                    .then(|| ())
+                    .ok_or(format!("bio should be no more than {} chars", MAX_BIO_LEN))?;
+            }
+            (self.links.len() <= MAX_PROFILE_LINKS)
+                .then(|| ())
+                .ok_or(format!("no more than {} links", MAX_PROFILE_LINKS))?;
+            for link in self.links.iter() {
+                validate_url(link)?;
+            }
+            if let Some(language) = &self.language {
+                let re = regex::Regex::new(LANGUAGE_REGEX).unwrap();
+                re.captures(language)
+                    .ok_or("Invalid language".to_string())?;
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetMinReputationArg {
+        pub community: String,
+        pub min_post_reputation: Option<i64>,
+        pub min_tag_reputation: Option<i64>,
+    }
+
+    /// like a thread or comment, or take the like back
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct ReactArg {
+        pub content: ContentId,
+        pub liked: bool,
+    }
+
+    /// accept a comment as the answer to the thread, the bounty is paid in whole tokens
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct AcceptAnswerArg {
+        pub comment: ContentId,
+        pub bounty: u128,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct TagThreadArg {
+        pub thread: ContentId,
+        pub tags: Vec<String>,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct PenalizeArg {
+        pub community: String,
+        pub account: AccountId,
+        pub points: u64,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetRateLimitArg {
+        pub community: String,
+        pub rate_limit: RateLimit,
+    }
+
+    /// `None` to follow the platform defaults
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetContentRulesArg {
+        pub community: String,
+        pub content_rules: Option<ContentRules>,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetImageHostsArg {
+        pub community: String,
+        pub hosts: Vec<String>,
+    }
+
+    impl SetImageHostsArg {
+        pub fn validate(&self) -> Result<(), String> {
+            let re = regex::Regex::new(HOST_REGEX).unwrap();
+            (self.hosts.len() <= MAX_IMAGE_HOSTS)
+                .then(|| ())
+                .ok_or(format!("No more than {} hosts are allowed", MAX_IMAGE_HOSTS))?;
+            self.hosts.iter().try_for_each(|h| {
+                re.is_match(h)
+                    .then(|| ())
+                    .ok_or(format!("Invalid host: {}", h))
+            })
+        }
+    }
+
+    /// the job runs first at `start_at`, or after an interval if `None`, then repeats
+    /// every `interval` seconds, e.g. 604800 for a weekly recap
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct AddAgentJobArg {
+        pub community: String,
+        pub title: String,
+        pub instruction: String,
+        pub interval: u64,
+        pub start_at: Option<u64>,
+    }
+
+    impl AddAgentJobArg {
+        /// the first run should be scheduled in `MAX_JOB_INTERVAL` from `now`
+        pub fn validate(&self, now: u64) -> Result<(), String> {
+            let title = self.title.trim();
+            let max_title_len = ContentRules::default().max_title_len as usize;
+            (!title.is_empty() && title.chars().count() <= max_title_len)
+                .then_some(())
+                .ok_or("Invalid job title".to_string())?;
+            (!self.instruction.trim().is_empty()
+                && self.instruction.len() <= MAX_JOB_INSTRUCTION_LEN)
+                .then_some(())
+                .ok_or(format!(
+                    "The instruction should be no more than {} bytes",
+                    MAX_JOB_INSTRUCTION_LEN
+                ))?;
+            (self.interval >= MIN_JOB_INTERVAL && self.interval <= MAX_JOB_INTERVAL)
+                .then_some(())
+                .ok_or(format!(
+                    "The interval should be in {}..={} seconds",
+                    MIN_JOB_INTERVAL, MAX_JOB_INTERVAL
+                ))?;
+            self.start_at
+                .map(|t| t <= now.saturating_add(MAX_JOB_INTERVAL))
+                .unwrap_or(true)
+                .then_some(())
+                .ok_or(format!("The job should start in {} seconds", MAX_JOB_INTERVAL))
+        }
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SummarizeThreadArg {
+        pub thread: ContentId,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetSummaryPriceArg {
+        pub community: String,
+        pub price: Option<u128>,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct RemoveAgentJobArg {
+        pub community: String,
+        pub id: u32,
+    }
+
+    /// the caps of the policy are in whole tokens
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetTransferPolicyArg {
+        pub community: String,
+        pub policy: TransferPolicy,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct RevertModerationArg {
+        pub community: String,
+        pub id: u32,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetAgentBudgetArg {
+        pub community: String,
+        pub budget: AgentBudget,
+    }
+
+    /// `None` to keep the current settings
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct UpdateAgentArg {
+        pub community: String,
+        pub prompt: Option<String>,
+        pub generation: Option<GenerationParams>,
+        pub tools: Option<Vec<String>>,
+    }
+
+    impl UpdateAgentArg {
+        pub fn validate(&self) -> Result<(), String> {
+            if let Some(generation) = &self.generation {
+                generation.validate()?;
+            }
+            Ok(())
+        }
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetModelsArg {
+        pub models: Vec<ModelPrice>,
+    }
+
+    /// propose a new owner of the community, `None` to cancel the pending proposal
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct ProposeOwnerArg {
+        pub community: String,
+        pub new_owner: Option<AccountId>,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct AcceptOwnershipArg {
+        pub community: String,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct SetLlmKeyArg {
+        pub llm_name: String,
+        pub key: String,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct AdminArg {
+        pub account: AccountId,
+    }
+
+    /// scan the contents from `from` and index the threads into the feed
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct BackfillFeedArg {
+        pub from: ContentId,
+        pub limit: u32,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct FreezeCommunityArg {
+        pub community: String,
+        pub until: u64,
+        pub reason: String,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct UnfreezeCommunityArg {
+        pub community: String,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct RetryActivationArg {
+        pub community: String,
+    }
+
+    #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
+    pub struct RefundActivationArg {
+        pub community: String,
+    }
+
     #[derive(Debug, Decode, Encode, Deserialize, Serialize, TypeInfo)]
     pub struct SetModeArg {
         pub community: String,
@@ -571,3 +1820,186 @@ pub struct RewardPayload {
     pub token_contract: AccountId,
     pub withdrawed: bool,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// the structs are encoded as their fields in order, so the records saved by the
+    /// baseline layouts are rebuilt from the fields they had
+    fn baseline_token() -> TokenMetadata {
+        TokenMetadata {
+            name: "Veforum".to_string(),
+            symbol: "VEF".to_string(),
+            total_issuance: 1_000_000,
+            decimals: 6,
+            new_issue: true,
+            contract: H160([1; 20]),
+            image: None,
+        }
+    }
+
+    fn baseline_community(vendor: Vec<u8>, status: Vec<u8>) -> Vec<u8> {
+        [
+            "0x01".to_string().encode(),
+            CommunityMode::Public.encode(),
+            "logo".to_string().encode(),
+            "veforum".to_string().encode(),
+            "slug".to_string().encode(),
+            "description".to_string().encode(),
+            baseline_token().encode(),
+            None::<AccountId>.encode(),
+            "prompt".to_string().encode(),
+            0u64.encode(),
+            0u64.encode(),
+            H160([2; 20]).encode(),
+            H160([3; 20]).encode(),
+            vendor,
+            "asst_1".to_string().encode(),
+            status,
+            1700000000i64.encode(),
+        ]
+        .concat()
+    }
+
+    #[test]
+    fn decode_baseline_account() {
+        let raw = [
+            7u64.encode(),
+            H160([1; 20]).encode(),
+            5u64.encode(),
+            Some("alice".to_string()).encode(),
+            1700000000u64.encode(),
+        ]
+        .concat();
+        let account = Account::decode(&mut &raw[..]).unwrap();
+        assert_eq!(account.nonce, 7);
+        assert_eq!(account.address, H160([1; 20]));
+        assert_eq!(account.alias, Some("alice".to_string()));
+        assert_eq!(account.last_post_at, 1700000000);
+        assert!(account.avatar.is_none());
+        assert!(account.links.is_empty());
+        assert!(account.language.is_none());
+    }
+
+    #[test]
+    fn decode_baseline_community() {
+        let raw = baseline_community(
+            (0u8, "sk-1".to_string()).encode(),
+            CommunityStatus::Active.encode(),
+        );
+        let community = Community::decode(&mut &raw[..]).unwrap();
+        assert_eq!(community.name, "veforum");
+        assert_eq!(community.creator, H160([2; 20]));
+        assert_eq!(community.status, CommunityStatus::Active);
+        assert_eq!(community.created_time, 1700000000);
+        assert!(community.min_post_reputation.is_none());
+        assert!(community.pending_owner.is_none());
+        assert!(community.image_hosts.is_empty());
+        assert_eq!(community.agent_tools, LEGACY_AGENT_TOOLS);
+        assert!(community.summary_price.is_none());
+        assert!(community.min_tag_reputation.is_none());
+    }
+
+    #[test]
+    fn decode_current_community() {
+        let raw = baseline_community(
+            (0u8, "sk-1".to_string()).encode(),
+            CommunityStatus::Active.encode(),
+        );
+        let mut community = Community::decode(&mut &raw[..]).unwrap();
+        community.agent_tools = vec![];
+        community.min_tag_reputation = Some(10);
+        let raw = community.encode();
+        let decoded = Community::decode(&mut &raw[..]).unwrap();
+        assert!(decoded.agent_tools.is_empty());
+        assert_eq!(decoded.min_tag_reputation, Some(10));
+        assert_eq!(decoded.encode(), raw);
+    }
+
+    #[test]
+    fn decode_baseline_status() {
+        let raw = (4u8, 1700000000u64).encode();
+        assert_eq!(
+            CommunityStatus::decode(&mut &raw[..]).unwrap(),
+            CommunityStatus::Frozen(1700000000, String::new())
+        );
+        let status = CommunityStatus::Frozen(1700000000, "spam".to_string());
+        let raw = status.encode();
+        assert_eq!(raw[0], 6);
+        assert_eq!(CommunityStatus::decode(&mut &raw[..]).unwrap(), status);
+        let raw = (5u8, "0xabc".to_string()).encode();
+        assert_eq!(
+            CommunityStatus::decode(&mut &raw[..]).unwrap(),
+            CommunityStatus::TokenIssued("0xabc".to_string())
+        );
+        let raw = baseline_community((0u8, "sk-1".to_string()).encode(), (4u8, 1u64).encode());
+        let community = Community::decode(&mut &raw[..]).unwrap();
+        assert_eq!(community.status, CommunityStatus::Frozen(1, String::new()));
+    }
+
+    #[test]
+    fn decode_baseline_vendor() {
+        let raw = (0u8, "sk-1".to_string()).encode();
+        let vendor = LlmVendor::decode(&mut &raw[..]).unwrap();
+        assert_eq!(vendor.name(), "OpenAI");
+        assert_eq!(vendor.key(), "sk-1");
+        assert_eq!(vendor.host(), "https://api.openai.com/v1");
+        let raw = vendor.encode();
+        assert_eq!(raw[0], 2);
+        let vendor = LlmVendor::decode(&mut &raw[..]).unwrap();
+        assert_eq!(vendor.host(), "https://api.openai.com/v1");
+        let raw = (1u8, "sk-2".to_string(), "https://api.deepseek.com".to_string()).encode();
+        let vendor = LlmVendor::decode(&mut &raw[..]).unwrap();
+        assert_eq!(vendor.name(), "DeepSeek");
+        assert_eq!(vendor.host(), "https://api.deepseek.com");
+    }
+
+    #[test]
+    fn decode_baseline_thread() {
+        let id = hex::encode((1u128 << 64 | 1 << 32).encode());
+        let raw = [
+            id.encode(),
+            "veforum".to_string().encode(),
+            "title".to_string().encode(),
+            b"content".to_vec().encode(),
+            vec!["https://i.imgur.com/a.png".to_string()].encode(),
+            H160([1; 20]).encode(),
+            Vec::<AccountId>::new().encode(),
+            "thread_1".to_string().encode(),
+            1700000000i64.encode(),
+        ]
+        .concat();
+        let thread = Thread::decode(&mut &raw[..]).unwrap();
+        assert_eq!(thread.id(), 1u128 << 64 | 1 << 32);
+        assert_eq!(thread.legacy_images, vec!["https://i.imgur.com/a.png"]);
+        assert_eq!(thread.llm_session_id, "thread_1");
+        assert!(thread.images.is_empty());
+        assert!(!thread.locked);
+        assert!(!thread.pinned);
+        assert!(thread.tags.is_empty());
+        assert!(thread.summary.is_none());
+        assert!(thread.accepted_answer.is_none());
+    }
+
+    #[test]
+    fn decode_baseline_comment() {
+        let id = hex::encode((1u128 << 64 | 1 << 32 | 2).encode());
+        let raw = [
+            id.encode(),
+            b"content".to_vec().encode(),
+            Vec::<String>::new().encode(),
+            H160([1; 20]).encode(),
+            vec![H160([2; 20])].encode(),
+            None::<String>.encode(),
+            1700000000i64.encode(),
+        ]
+        .concat();
+        let comment = Comment::decode(&mut &raw[..]).unwrap();
+        assert_eq!(comment.id(), 1u128 << 64 | 1 << 32 | 2);
+        assert_eq!(comment.mention, vec![H160([2; 20])]);
+        assert_eq!(comment.created_time, 1700000000);
+        assert!(comment.images.is_empty());
+        assert!(!comment.hidden);
+    }
+}
@@ -6,6 +6,28 @@ use rocksdb::DB;
 use vemodel::*;
 use vrs_core_sdk::NucleusId;
 
+const MAX_INDEXED_CONTENT: usize = 1024 * 1024;
+
+/// index the decoded text with its format instead of the raw envelope,
+/// the contents which couldn't be decoded are skipped
+fn to_document<T: serde::Serialize>(
+    raw: &T,
+    id: ContentId,
+    content: &[u8],
+) -> anyhow::Result<Option<serde_json::Value>> {
+    let (format, text) = match vemodel::codec::decode(content, MAX_INDEXED_CONTENT) {
+        Ok(decoded) => decoded,
+        Err(e) => {
+            println!("Skip indexing {}: {:?}", id, e);
+            return Ok(None);
+        }
+    };
+    let mut doc = serde_json::to_value(raw)?;
+    doc["content"] = serde_json::Value::String(text);
+    doc["format"] = serde_json::to_value(format)?;
+    Ok(Some(doc))
+}
+
 pub async fn index_event(
     origin: &HttpClient,
     db: &DB,
@@ -51,13 +73,13 @@ pub async fn index_event(
                         let comment = Comment::decode(&mut &content[..])
                             .map_err(|_| anyhow::anyhow!("decode comment failed"))?;
                         if !storage::exists(&db, id.to_be_bytes()) {
-                            comments.push(comment);
+                            comments.extend(to_document(&comment, *id, &comment.content)?);
                         }
                     } else {
                         let thread = Thread::decode(&mut &content[..])
                             .map_err(|_| anyhow::anyhow!("decode thread failed"))?;
                         if !storage::exists(&db, id.to_be_bytes()) {
-                            threads.push(thread);
+                            threads.extend(to_document(&thread, *id, &thread.content)?);
                         }
                     }
                 }
@@ -88,8 +110,10 @@ pub async fn index_event(
                     storage::save_contents(&db, &[(content_id, raw.clone())])?;
                     let comment = Comment::decode(&mut &raw[..])
                         .map_err(|_| anyhow::anyhow!("decode comment failed"))?;
-                    let index = indexer.index("comment");
-                    index.add_documents(&[comment], Some("id")).await?;
+                    if let Some(document) = to_document(&comment, content_id, &comment.content)? {
+                        let index = indexer.index("comment");
+                        index.add_documents(&[document], Some("id")).await?;
+                    }
                 }
             }
             storage::save_event(&db, id, Event::CommentPosted(content_id))?;
@@ -100,6 +124,45 @@ pub async fn index_event(
             index.delete_document(content_id).await?;
             storage::save_event(&db, id, Event::CommentDeleted(content_id))?;
         }
+        Event::ThreadUpdated(content_id) | Event::ThreadSummarized(content_id) => {
+            let content = rpc::get_content(origin, nucleus_id, content_id)
+                .await
+                .map_err(|_| anyhow::anyhow!("fetch thread failed"))?;
+            if let Some(raw) = content {
+                storage::save_contents(&db, &[(content_id, raw.clone())])?;
+                let thread = Thread::decode(&mut &raw[..])
+                    .map_err(|_| anyhow::anyhow!("decode thread failed"))?;
+                if let Some(document) = to_document(&thread, content_id, &thread.content)? {
+                    let index = indexer.index("thread");
+                    index.add_documents(&[document], Some("id")).await?;
+                }
+            }
+            storage::save_event(&db, id, event)?;
+        }
+        Event::CommentUpdated(content_id) => {
+            let content = rpc::get_content(origin, nucleus_id, content_id)
+                .await
+                .map_err(|_| anyhow::anyhow!("fetch comment failed"))?;
+            if let Some(raw) = content {
+                storage::save_contents(&db, &[(content_id, raw.clone())])?;
+                let comment = Comment::decode(&mut &raw[..])
+                    .map_err(|_| anyhow::anyhow!("decode comment failed"))?;
+                if let Some(document) = to_document(&comment, content_id, &comment.content)? {
+                    let index = indexer.index("comment");
+                    index.add_documents(&[document], Some("id")).await?;
+                }
+            }
+            storage::save_event(&db, id, Event::CommentUpdated(content_id))?;
+        }
+        Event::AccountUpdated(account_id) => {
+            let account = rpc::get_account_info(origin, nucleus_id, account_id)
+                .await
+                .inspect_err(|e| println!("Error: {:?}", e))
+                .map_err(|_| anyhow::anyhow!("fetch account failed"))?;
+            let index = indexer.index("user");
+            index.add_documents(&[account], Some("address")).await?;
+            storage::save_event(&db, id, Event::AccountUpdated(account_id))?;
+        }
     }
     Ok(())
 }
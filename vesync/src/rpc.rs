@@ -19,6 +19,21 @@ pub async fn get_community<T: ClientT>(
     Result::<Option<Community>, String>::decode(&mut &hex[..])?.map_err(|e| e.into())
 }
 
+pub async fn get_account_info<T: ClientT>(
+    client: &T,
+    nucleus_id: &AccountId,
+    account_id: vemodel::AccountId,
+) -> Result<Account, Box<dyn std::error::Error>> {
+    let params = rpc_params![
+        nucleus_id.to_string(),
+        "get_account_info",
+        hex::encode(account_id.encode())
+    ];
+    let hex_str: String = client.request("nucleus_get", params).await?;
+    let hex = hex::decode(&hex_str)?;
+    Result::<Account, String>::decode(&mut &hex[..])?.map_err(|e| e.into())
+}
+
 pub async fn get_contents<T: ClientT>(
     client: &T,
     nucleus_id: &AccountId,
@@ -109,7 +109,7 @@ pub async fn set_settings(client: &Client) {
     let thread_settings = Settings::default()
         .with_filterable_attributes(["id", "author"])
         .with_sortable_attributes(["created_time"])
-        .with_searchable_attributes(["id", "author", "title"]);
+        .with_searchable_attributes(["id", "author", "title", "summary"]);
     thread.set_settings(&thread_settings).await.unwrap();
 
     let comment = client.index("comment");
@@ -119,6 +119,12 @@ pub async fn set_settings(client: &Client) {
         .with_sortable_attributes(["created_time"]);
     comment.set_settings(&comment_settings).await.unwrap();
 
+    let user = client.index("user");
+    let user_settings = Settings::default()
+        .with_filterable_attributes(["address", "language"])
+        .with_searchable_attributes(["alias", "address", "bio"]);
+    user.set_settings(&user_settings).await.unwrap();
+
     enable_experimental_features(client.get_host())
         .await
         .unwrap();